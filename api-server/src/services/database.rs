@@ -1,33 +1,320 @@
 use anyhow::Result;
-use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
+use r2d2::{CustomizeConnection, Pool};
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, Connection, OptionalExtension, Params, Result as SqliteResult, Row};
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
 
 use crate::models::{
     agent::{Agent, CreateAgentRequest},
     claude::SessionRecord,
 };
 
+/// Maps a single query result row onto a model type, so `get_agents` /
+/// `get_agent` / `get_sessions` / `get_session` don't each repeat the same
+/// column-by-column construction.
+trait FromRow: Sized {
+    fn from_row(row: &Row) -> SqliteResult<Self>;
+}
+
+impl FromRow for Agent {
+    fn from_row(row: &Row) -> SqliteResult<Self> {
+        Ok(Agent {
+            id: Some(row.get(0)?),
+            name: row.get(1)?,
+            icon: row.get(2)?,
+            system_prompt: row.get(3)?,
+            default_task: row.get(4)?,
+            model: row.get(5)?,
+            hooks: row.get(6)?,
+            created_at: row.get(7)?,
+            updated_at: row.get(8)?,
+        })
+    }
+}
+
+impl FromRow for SessionRecord {
+    fn from_row(row: &Row) -> SqliteResult<Self> {
+        Ok(SessionRecord {
+            id: row.get(0)?,
+            task: row.get(1)?,
+            model: row.get(2)?,
+            project_path: row.get(3)?,
+            session_id: row.get(4)?,
+            created_at: row.get(5)?,
+            status: row.get(6)?,
+            output: row.get(7)?,
+        })
+    }
+}
+
+/// A prior `status`/`output` a session passed through, recorded by the
+/// `agent_runs_history_on_update`/`agent_runs_history_on_delete` triggers
+/// whenever a row in `agent_runs` changes or is removed.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct SessionHistoryEntry {
+    pub session_id: String,
+    pub status: String,
+    pub output: Option<String>,
+    pub operation: String,
+    pub changed_at: String,
+}
+
+impl FromRow for SessionHistoryEntry {
+    fn from_row(row: &Row) -> SqliteResult<Self> {
+        Ok(SessionHistoryEntry {
+            session_id: row.get(0)?,
+            status: row.get(1)?,
+            output: row.get(2)?,
+            operation: row.get(3)?,
+            changed_at: row.get(4)?,
+        })
+    }
+}
+
+impl FromRow for (String, String) {
+    fn from_row(row: &Row) -> SqliteResult<Self> {
+        Ok((row.get(0)?, row.get(1)?))
+    }
+}
+
+/// Prepare `sql`, run it, and map every row through [`FromRow`].
+fn query_all<T: FromRow, P: Params>(conn: &Connection, sql: &str, params: P) -> Result<Vec<T>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt
+        .query_map(params, |row| T::from_row(row))?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(rows)
+}
+
+/// Like [`query_all`], but for queries expected to return at most one row.
+fn query_opt<T: FromRow, P: Params>(conn: &Connection, sql: &str, params: P) -> Result<Option<T>> {
+    let mut stmt = conn.prepare(sql)?;
+    let row = stmt.query_row(params, |row| T::from_row(row)).optional()?;
+    Ok(row)
+}
+
+/// Default number of pooled connections when callers don't care to tune it.
+const DEFAULT_POOL_SIZE: u32 = 10;
+
+/// How long a connection will wait on `SQLITE_BUSY` before giving up, in
+/// milliseconds. Generous enough to ride out a concurrent writer without
+/// surfacing spurious busy errors to callers.
+const BUSY_TIMEOUT_MS: u32 = 5_000;
+
+/// Applied to every pooled connection when it's first opened, so cascade
+/// deletes (`agent_runs.agent_id REFERENCES agents(id) ON DELETE CASCADE`)
+/// are actually enforced, concurrent writers don't immediately hit
+/// `SQLITE_BUSY`, and readers aren't blocked by writers under WAL mode.
+#[derive(Debug)]
+struct ConnectionOptions;
+
+impl CustomizeConnection<Connection, rusqlite::Error> for ConnectionOptions {
+    fn on_acquire(&self, conn: &mut Connection) -> std::result::Result<(), rusqlite::Error> {
+        conn.pragma_update(None, "foreign_keys", true)?;
+        conn.pragma_update(None, "busy_timeout", BUSY_TIMEOUT_MS)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        Ok(())
+    }
+}
+
+/// One forward step in the schema's history. `up` runs inside a transaction,
+/// so a failure partway through a step leaves the schema at the previous
+/// version rather than half-migrated.
+struct Migration {
+    version: u32,
+    description: &'static str,
+    up: &'static str,
+}
+
+/// Ordered schema history, oldest first. `init_database` applies every
+/// migration with a version greater than `PRAGMA user_version` and bumps
+/// `user_version` to match, so upgrading a user's `~/.claude/opcode.db`
+/// across releases no longer requires manual SQL surgery.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create agents, agent_runs, mcp_servers, and slash_commands tables",
+        up: "
+        CREATE TABLE IF NOT EXISTS agents (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            icon TEXT NOT NULL,
+            system_prompt TEXT NOT NULL,
+            default_task TEXT,
+            model TEXT NOT NULL,
+            hooks TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS agent_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            agent_id INTEGER NOT NULL,
+            agent_name TEXT NOT NULL,
+            agent_icon TEXT NOT NULL,
+            task TEXT NOT NULL,
+            model TEXT NOT NULL,
+            project_path TEXT NOT NULL,
+            session_id TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            status TEXT NOT NULL DEFAULT 'running',
+            output TEXT,
+            FOREIGN KEY (agent_id) REFERENCES agents (id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS mcp_servers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            command TEXT NOT NULL,
+            args TEXT NOT NULL,
+            env TEXT,
+            enabled BOOLEAN NOT NULL DEFAULT 1,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS slash_commands (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            command TEXT NOT NULL,
+            description TEXT,
+            enabled BOOLEAN NOT NULL DEFAULT 1,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+    ",
+    },
+    Migration {
+        version: 2,
+        description: "add an agent_runs_history audit log, populated by an AFTER UPDATE trigger. \
+                       There's no `projects` table in this tree yet, so project history isn't covered here.",
+        up: "
+        CREATE TABLE IF NOT EXISTS agent_runs_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            run_id INTEGER NOT NULL,
+            session_id TEXT NOT NULL,
+            status TEXT NOT NULL,
+            output TEXT,
+            operation TEXT NOT NULL,
+            changed_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TRIGGER IF NOT EXISTS agent_runs_history_on_update
+        AFTER UPDATE ON agent_runs
+        FOR EACH ROW
+        WHEN OLD.status IS NOT NEW.status OR OLD.output IS NOT NEW.output
+        BEGIN
+            INSERT INTO agent_runs_history (run_id, session_id, status, output, operation)
+            VALUES (OLD.id, OLD.session_id, OLD.status, OLD.output, 'update');
+        END;
+    ",
+    },
+    Migration {
+        version: 3,
+        description: "drop the denormalized agent_name/agent_icon columns from agent_runs and add \
+                       agent_runs_resolved, a view that joins agents back in so listings always \
+                       reflect the live agent identity",
+        up: "
+        ALTER TABLE agent_runs DROP COLUMN agent_name;
+        ALTER TABLE agent_runs DROP COLUMN agent_icon;
+
+        CREATE VIEW IF NOT EXISTS agent_runs_resolved AS
+        SELECT
+            ar.id,
+            ar.task,
+            ar.model,
+            ar.project_path,
+            ar.session_id,
+            ar.created_at,
+            ar.status,
+            ar.output,
+            COALESCE(a.name, 'Claude Code') AS agent_name,
+            COALESCE(a.icon, 'ðŸ¤–') AS agent_icon
+        FROM agent_runs ar
+        LEFT JOIN agents a ON a.id = ar.agent_id;
+    ",
+    },
+    Migration {
+        version: 4,
+        description: "add the agent_runs_history_on_delete trigger that should have shipped \
+                       alongside migration 2's AFTER UPDATE trigger, so a deleted agent_runs row \
+                       is preserved in agent_runs_history the same way an updated one is",
+        up: "
+        CREATE TRIGGER IF NOT EXISTS agent_runs_history_on_delete
+        AFTER DELETE ON agent_runs
+        FOR EACH ROW
+        BEGIN
+            INSERT INTO agent_runs_history (run_id, session_id, status, output, operation)
+            VALUES (OLD.id, OLD.session_id, OLD.status, OLD.output, 'delete');
+        END;
+    ",
+    },
+];
+
+/// Terminal statuses eligible for retention pruning — a run that's still
+/// `running` is never pruned regardless of age.
+const TERMINAL_STATUSES: &str = "('completed', 'failed')";
+
+/// How long to keep completed/failed agent runs around before `prune_sessions`
+/// removes them.
+#[derive(Debug, Clone, Copy)]
+pub struct RetentionPolicy {
+    /// Prune terminal sessions whose `created_at` is older than this many
+    /// days. `None` disables age-based pruning.
+    pub max_age_days: Option<i64>,
+    /// Keep at most this many terminal sessions regardless of age, pruning
+    /// the oldest excess first. `None` disables count-based pruning.
+    pub max_count: Option<usize>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        RetentionPolicy {
+            max_age_days: Some(30),
+            max_count: None,
+        }
+    }
+}
+
 pub struct DatabaseService {
-    connection: Arc<Mutex<Connection>>,
+    pool: Pool<SqliteConnectionManager>,
+    retention: RetentionPolicy,
 }
 
 impl DatabaseService {
     pub fn new() -> Result<Self> {
+        Self::with_config(DEFAULT_POOL_SIZE)
+    }
+
+    /// Like [`Self::new`], but with a caller-chosen pool size — useful when
+    /// the default of `DEFAULT_POOL_SIZE` connections doesn't fit an
+    /// install's expected concurrency.
+    pub fn with_config(pool_size: u32) -> Result<Self> {
+        Self::with_retention_policy(pool_size, RetentionPolicy::default())
+    }
+
+    /// Like [`Self::with_config`], but with a caller-chosen [`RetentionPolicy`]
+    /// instead of the 30-day default.
+    pub fn with_retention_policy(pool_size: u32, retention: RetentionPolicy) -> Result<Self> {
         let db_path = Self::get_db_path()?;
-        
+
         // Ensure parent directory exists
         if let Some(parent) = db_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
 
-        let conn = Connection::open(&db_path)?;
-        
-        let service = DatabaseService {
-            connection: Arc::new(Mutex::new(conn)),
-        };
-        
+        let manager = SqliteConnectionManager::file(&db_path);
+        let pool = Pool::builder()
+            .max_size(pool_size)
+            .connection_customizer(Box::new(ConnectionOptions))
+            .build(manager)?;
+
+        let service = DatabaseService { pool, retention };
+
         service.init_database()?;
+        if let Err(e) = service.prune_sessions() {
+            tracing::warn!("session retention prune failed: {e}");
+        }
         Ok(service)
     }
 
@@ -36,79 +323,39 @@ impl DatabaseService {
         Ok(home_dir.join(".claude").join("opcode.db"))
     }
 
+    /// Bring the schema up to the latest migration, applying each pending
+    /// step transactionally so a mid-migration error can't leave the
+    /// database half-upgraded.
     fn init_database(&self) -> Result<()> {
-        let conn = self.connection.lock().unwrap();
-        
-        // Create agents table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS agents (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT NOT NULL UNIQUE,
-                icon TEXT NOT NULL,
-                system_prompt TEXT NOT NULL,
-                default_task TEXT,
-                model TEXT NOT NULL,
-                hooks TEXT,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
+        let mut conn = self.pool.get()?;
 
-        // Create agent_runs table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS agent_runs (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                agent_id INTEGER NOT NULL,
-                agent_name TEXT NOT NULL,
-                agent_icon TEXT NOT NULL,
-                task TEXT NOT NULL,
-                model TEXT NOT NULL,
-                project_path TEXT NOT NULL,
-                session_id TEXT NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                status TEXT NOT NULL DEFAULT 'running',
-                output TEXT,
-                FOREIGN KEY (agent_id) REFERENCES agents (id) ON DELETE CASCADE
-            )",
-            [],
-        )?;
-
-        // Create mcp_servers table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS mcp_servers (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT NOT NULL UNIQUE,
-                command TEXT NOT NULL,
-                args TEXT NOT NULL,
-                env TEXT,
-                enabled BOOLEAN NOT NULL DEFAULT 1,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
+        let current_version: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
 
-        // Create slash_commands table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS slash_commands (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT NOT NULL UNIQUE,
-                command TEXT NOT NULL,
-                description TEXT,
-                enabled BOOLEAN NOT NULL DEFAULT 1,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
+        for migration in MIGRATIONS.iter().filter(|m| m.version > current_version) {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration.up).map_err(|e| {
+                anyhow::anyhow!(
+                    "migration {} ({}) failed: {e}",
+                    migration.version,
+                    migration.description
+                )
+            })?;
+            tx.pragma_update(None, "user_version", migration.version)?;
+            tx.commit()?;
+        }
 
         Ok(())
     }
 
+    /// The highest migration version currently applied to this database.
+    pub fn current_schema_version(&self) -> Result<u32> {
+        let conn = self.pool.get()?;
+        Ok(conn.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+    }
+
     // Agent operations
     pub fn create_agent(&self, request: CreateAgentRequest) -> Result<Agent> {
-        let conn = self.connection.lock().unwrap();
+        let conn = self.pool.get()?;
         let now = chrono::Utc::now().to_rfc3339();
         
         conn.execute(
@@ -143,62 +390,34 @@ impl DatabaseService {
     }
 
     pub fn get_agents(&self) -> Result<Vec<Agent>> {
-        let conn = self.connection.lock().unwrap();
-        let mut stmt = conn.prepare(
+        let conn = self.pool.get()?;
+        query_all(
+            &conn,
             "SELECT id, name, icon, system_prompt, default_task, model,
-             hooks, created_at, updated_at FROM agents ORDER BY created_at DESC"
-        )?;
-
-        let agents = stmt.query_map([], |row| {
-            Ok(Agent {
-                id: Some(row.get(0)?),
-                name: row.get(1)?,
-                icon: row.get(2)?,
-                system_prompt: row.get(3)?,
-                default_task: row.get(4)?,
-                model: row.get(5)?,
-                hooks: row.get(6)?,
-                created_at: row.get(7)?,
-                updated_at: row.get(8)?,
-            })
-        })?.collect::<SqliteResult<Vec<_>>>()?;
-
-        Ok(agents)
+             hooks, created_at, updated_at FROM agents ORDER BY created_at DESC",
+            [],
+        )
     }
 
     pub fn get_agent(&self, id: i64) -> Result<Option<Agent>> {
-        let conn = self.connection.lock().unwrap();
-        let mut stmt = conn.prepare(
+        let conn = self.pool.get()?;
+        query_opt(
+            &conn,
             "SELECT id, name, icon, system_prompt, default_task, model,
-             hooks, created_at, updated_at FROM agents WHERE id = ?1"
-        )?;
-
-        let agent = stmt.query_row([id], |row| {
-            Ok(Agent {
-                id: Some(row.get(0)?),
-                name: row.get(1)?,
-                icon: row.get(2)?,
-                system_prompt: row.get(3)?,
-                default_task: row.get(4)?,
-                model: row.get(5)?,
-                hooks: row.get(6)?,
-                created_at: row.get(7)?,
-                updated_at: row.get(8)?,
-            })
-        }).optional()?;
-
-        Ok(agent)
+             hooks, created_at, updated_at FROM agents WHERE id = ?1",
+            [id],
+        )
     }
 
     pub fn delete_agent(&self, id: i64) -> Result<bool> {
-        let conn = self.connection.lock().unwrap();
+        let conn = self.pool.get()?;
         let affected = conn.execute("DELETE FROM agents WHERE id = ?1", [id])?;
         Ok(affected > 0)
     }
 
     // Session operations
     pub fn create_session_record(&self, session_id: &str, task: &str, project_path: &str, model: &str) -> Result<()> {
-        let conn = self.connection.lock().unwrap();
+        let conn = self.pool.get()?;
         let now = chrono::Utc::now().to_rfc3339();
         
         // Create a dummy agent first if it doesn't exist (agent_id = 1)
@@ -209,8 +428,8 @@ impl DatabaseService {
         );
         
         conn.execute(
-            "INSERT INTO agent_runs (agent_id, agent_name, agent_icon, task, model, project_path, session_id, created_at)
-             VALUES (1, 'Claude Code', 'ðŸ¤–', ?1, ?2, ?3, ?4, ?5)",
+            "INSERT INTO agent_runs (agent_id, task, model, project_path, session_id, created_at)
+             VALUES (1, ?1, ?2, ?3, ?4, ?5)",
             params![task, model, project_path, session_id, now],
         )?;
 
@@ -218,7 +437,7 @@ impl DatabaseService {
     }
 
     pub fn update_session_status(&self, session_id: &str, status: &str, output: Option<&str>) -> Result<()> {
-        let conn = self.connection.lock().unwrap();
+        let conn = self.pool.get()?;
         
         if let Some(output) = output {
             conn.execute(
@@ -236,79 +455,108 @@ impl DatabaseService {
     }
 
     pub fn get_sessions(&self, project_path: Option<&str>) -> Result<Vec<SessionRecord>> {
-        let conn = self.connection.lock().unwrap();
-        
-        let mut sessions = Vec::new();
-        
-        if let Some(path) = project_path {
-            let mut stmt = conn.prepare(
-                "SELECT id, task, model, project_path, session_id, created_at, status, output 
-                 FROM agent_runs WHERE project_path = ? ORDER BY created_at DESC"
-            )?;
-            
-            let session_iter = stmt.query_map([path], |row| {
-                Ok(SessionRecord {
-                    id: row.get(0)?,
-                    task: row.get(1)?,
-                    model: row.get(2)?,
-                    project_path: row.get(3)?,
-                    session_id: row.get(4)?,
-                    created_at: row.get(5)?,
-                    status: row.get(6)?,
-                    output: row.get(7)?,
-                })
-            })?;
-            
-            for session in session_iter {
-                sessions.push(session?);
-            }
-        } else {
-            let mut stmt = conn.prepare(
-                "SELECT id, task, model, project_path, session_id, created_at, status, output 
-                 FROM agent_runs ORDER BY created_at DESC"
-            )?;
-            
-            let session_iter = stmt.query_map([], |row| {
-                Ok(SessionRecord {
-                    id: row.get(0)?,
-                    task: row.get(1)?,
-                    model: row.get(2)?,
-                    project_path: row.get(3)?,
-                    session_id: row.get(4)?,
-                    created_at: row.get(5)?,
-                    status: row.get(6)?,
-                    output: row.get(7)?,
-                })
-            })?;
-            
-            for session in session_iter {
-                sessions.push(session?);
-            }
+        let conn = self.pool.get()?;
+
+        const BASE: &str =
+            "SELECT id, task, model, project_path, session_id, created_at, status, output FROM agent_runs_resolved";
+
+        match project_path {
+            Some(path) => query_all(
+                &conn,
+                &format!("{BASE} WHERE project_path = ? ORDER BY created_at DESC"),
+                [path],
+            ),
+            None => query_all(&conn, &format!("{BASE} ORDER BY created_at DESC"), []),
         }
-        
-        Ok(sessions)
     }
 
     pub fn get_session(&self, session_id: &str) -> Result<Option<SessionRecord>> {
-        let conn = self.connection.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, task, model, project_path, session_id, created_at, status, output 
-             FROM agent_runs WHERE session_id = ?1"
-        )?;
+        let conn = self.pool.get()?;
+        query_opt(
+            &conn,
+            "SELECT id, task, model, project_path, session_id, created_at, status, output
+             FROM agent_runs_resolved WHERE session_id = ?1",
+            [session_id],
+        )
+    }
+
+    /// The ordered history of `status`/`output` changes a session passed
+    /// through, oldest first, as recorded by the `agent_runs_history`
+    /// trigger.
+    pub fn get_session_history(&self, session_id: &str) -> Result<Vec<SessionHistoryEntry>> {
+        let conn = self.pool.get()?;
+        query_all(
+            &conn,
+            "SELECT session_id, status, output, operation, changed_at
+             FROM agent_runs_history WHERE session_id = ?1 ORDER BY changed_at ASC",
+            [session_id],
+        )
+    }
+
+    /// Delete terminal (completed/failed) sessions that fall outside
+    /// `self.retention`, also removing their on-disk `.jsonl` transcript
+    /// (best-effort). Returns how many sessions were pruned. Safe to call
+    /// opportunistically — a policy with both fields `None` is a no-op.
+    pub fn prune_sessions(&self) -> Result<usize> {
+        let conn = self.pool.get()?;
+        let mut to_remove: Vec<(String, String)> = Vec::new();
+
+        if let Some(max_age_days) = self.retention.max_age_days {
+            let cutoff = (chrono::Utc::now() - chrono::Duration::days(max_age_days)).to_rfc3339();
+            to_remove.extend(query_all::<(String, String), _>(
+                &conn,
+                &format!(
+                    "SELECT session_id, project_path FROM agent_runs
+                     WHERE status IN {TERMINAL_STATUSES} AND created_at < ?1"
+                ),
+                [cutoff],
+            )?);
+        }
+
+        if let Some(max_count) = self.retention.max_count {
+            to_remove.extend(query_all::<(String, String), _>(
+                &conn,
+                &format!(
+                    "SELECT session_id, project_path FROM agent_runs
+                     WHERE status IN {TERMINAL_STATUSES}
+                     ORDER BY created_at DESC LIMIT -1 OFFSET ?1"
+                ),
+                [max_count as i64],
+            )?);
+        }
+
+        to_remove.sort();
+        to_remove.dedup();
+
+        for (session_id, _) in &to_remove {
+            conn.execute(
+                "DELETE FROM agent_runs WHERE session_id = ?1",
+                params![session_id],
+            )?;
+        }
+
+        for (session_id, project_path) in &to_remove {
+            Self::remove_session_transcript(project_path, session_id);
+        }
+
+        Ok(to_remove.len())
+    }
+
+    /// Best-effort removal of a pruned session's `.jsonl` transcript under
+    /// `~/.claude/projects/<id>/`. Failures are swallowed — a missing or
+    /// already-removed file shouldn't fail the surrounding DB prune.
+    fn remove_session_transcript(project_path: &str, session_id: &str) {
+        let Some(home_dir) = dirs::home_dir() else {
+            return;
+        };
+
+        let project_dir = project_path.replace('/', "-");
+        let transcript = home_dir
+            .join(".claude")
+            .join("projects")
+            .join(project_dir)
+            .join(format!("{session_id}.jsonl"));
 
-        let session = stmt.query_row([session_id], |row| {
-            Ok(SessionRecord {
-                id: row.get(0)?,
-                task: row.get(1)?,
-                model: row.get(2)?,
-                project_path: row.get(3)?,
-                session_id: row.get(4)?,
-                created_at: row.get(5)?,
-                status: row.get(6)?,
-                output: row.get(7)?,
-            })
-        }).optional()?;
-
-        Ok(session)
+        let _ = std::fs::remove_file(transcript);
     }
 }
\ No newline at end of file
@@ -0,0 +1,315 @@
+use crate::api::jwt::{decode_jwt, AuthUser};
+use crate::api::{ApiError, ApiResult, AppState};
+use axum::extract::{FromRequestParts, Request, State};
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use axum::middleware::Next;
+use axum::response::Response;
+use axum_extra::extract::CookieJar;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// The cookie [`handlers::auth::login_handler`](crate::api::handlers::auth::login_handler)
+/// sets on a successful login, so a browser session can reuse it on
+/// subsequent requests without holding the token in JS-accessible storage.
+pub const SESSION_COOKIE: &str = "opcode_session";
+
+/// What an API key is allowed to do. Checked with [`ApiKeyContext::require`]
+/// from inside a handler, since the flat [`auth_middleware`] authenticates
+/// every `/api` request but has no route metadata telling it which scope a
+/// given handler actually needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiKeyScope {
+    AgentsRead,
+    AgentsWrite,
+    KeysAdmin,
+}
+
+impl ApiKeyScope {
+    fn as_str(self) -> &'static str {
+        match self {
+            ApiKeyScope::AgentsRead => "agents:read",
+            ApiKeyScope::AgentsWrite => "agents:write",
+            ApiKeyScope::KeysAdmin => "keys:admin",
+        }
+    }
+
+    fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "agents:read" => Some(ApiKeyScope::AgentsRead),
+            "agents:write" => Some(ApiKeyScope::AgentsWrite),
+            "keys:admin" => Some(ApiKeyScope::KeysAdmin),
+            _ => None,
+        }
+    }
+}
+
+/// The authenticated caller, attached to the request's extensions by
+/// [`auth_middleware`] so handlers can pull it out with the `Extension`
+/// extractor and enforce their own scope requirement.
+#[derive(Debug, Clone)]
+pub struct ApiKeyContext {
+    pub key_id: i64,
+    pub name: String,
+    scopes: Vec<ApiKeyScope>,
+}
+
+impl ApiKeyContext {
+    pub fn require(&self, scope: ApiKeyScope) -> ApiResult<()> {
+        if self.scopes.contains(&scope) {
+            Ok(())
+        } else {
+            Err(ApiError::Forbidden(format!(
+                "API key '{}' is missing required scope: {}",
+                self.name,
+                scope.as_str()
+            )))
+        }
+    }
+}
+
+/// Either credential kind [`auth_middleware`] accepts, for handlers that
+/// should be reachable by a machine API key *or* a logged-in human -
+/// `/api/auth/keys` is the case in point, since otherwise a freshly
+/// bootstrapped admin who only ever logs in through
+/// `handlers::auth::login_handler` would have no way to mint the first
+/// non-bootstrap key. Extracted directly (like [`AuthUser`]) rather than
+/// via `Extension<T>`, since which of the two extensions is present
+/// depends on which credential the caller used.
+pub enum AdminContext {
+    ApiKey(ApiKeyContext),
+    User(AuthUser),
+}
+
+impl AdminContext {
+    /// An API key must carry `keys:admin` explicitly; there's no scopes
+    /// concept for login accounts; since the only way into `users` is
+    /// [`super::users::bootstrap_admin_user_if_empty`] or
+    /// [`super::users::create_user`] called by someone who already holds
+    /// one, a logged-in user is trusted outright.
+    pub fn require_admin(&self) -> ApiResult<()> {
+        match self {
+            AdminContext::ApiKey(ctx) => ctx.require(ApiKeyScope::KeysAdmin),
+            AdminContext::User(_) => Ok(()),
+        }
+    }
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for AdminContext
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(ctx) = parts.extensions.get::<ApiKeyContext>() {
+            return Ok(AdminContext::ApiKey(ctx.clone()));
+        }
+        if let Some(user) = parts.extensions.get::<AuthUser>() {
+            return Ok(AdminContext::User(user.clone()));
+        }
+        Err(ApiError::Unauthorized("missing or invalid credentials".to_string()))
+    }
+}
+
+const KEY_PREFIX: &str = "opk_";
+
+/// Creates the `api_keys` table if it doesn't exist yet. Called alongside
+/// the other manual table creation in `handle_api_mode`, the same way
+/// `app_settings` is set up there.
+pub fn ensure_api_keys_table(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS api_keys (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            key_hash TEXT NOT NULL UNIQUE,
+            scopes TEXT NOT NULL,
+            revoked BOOLEAN NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Generates a fresh, high-entropy API key. Keys are random bearer tokens
+/// rather than user-chosen passwords, so a plain SHA-256 hash is enough to
+/// keep the plaintext out of the database — there's no need for a slow,
+/// salted KDF like argon2 the way there would be for something guessable.
+fn generate_key() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("{}{}", KEY_PREFIX, hex::encode(bytes))
+}
+
+fn hash_key(raw: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(raw.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Compares two hashes without short-circuiting on the first mismatched
+/// byte, so a timing attack can't be used to recover a valid key hash one
+/// byte at a time.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Mints a new key with the given scopes, storing only its hash, and
+/// returns the plaintext key alongside its row id. The plaintext is never
+/// persisted and can't be recovered later — the caller must save it now.
+pub fn mint_api_key(
+    conn: &rusqlite::Connection,
+    name: &str,
+    scopes: &[ApiKeyScope],
+) -> rusqlite::Result<(i64, String)> {
+    let raw = generate_key();
+    let hash = hash_key(&raw);
+    let scopes_str = scopes
+        .iter()
+        .map(|s| s.as_str())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    conn.execute(
+        "INSERT INTO api_keys (name, key_hash, scopes) VALUES (?1, ?2, ?3)",
+        rusqlite::params![name, hash, scopes_str],
+    )?;
+
+    Ok((conn.last_insert_rowid(), raw))
+}
+
+/// Marks a key revoked so it's rejected on the next request that presents
+/// it. Returns `false` if no key with that id exists.
+pub fn revoke_api_key(conn: &rusqlite::Connection, id: i64) -> rusqlite::Result<bool> {
+    let affected = conn.execute(
+        "UPDATE api_keys SET revoked = 1 WHERE id = ?1",
+        rusqlite::params![id],
+    )?;
+    Ok(affected > 0)
+}
+
+/// If no keys exist yet (fresh database), mints one with every scope and
+/// prints it once so an operator can bootstrap further keys through the
+/// `/api/auth/keys` endpoints. Without this there would be no way to call
+/// those endpoints in the first place, since they require `keys:admin`.
+pub fn bootstrap_admin_key_if_empty(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    let count: i64 = conn.query_row("SELECT COUNT(*) FROM api_keys", [], |row| row.get(0))?;
+    if count > 0 {
+        return Ok(());
+    }
+
+    let (_, raw) = mint_api_key(
+        conn,
+        "bootstrap",
+        &[
+            ApiKeyScope::AgentsRead,
+            ApiKeyScope::AgentsWrite,
+            ApiKeyScope::KeysAdmin,
+        ],
+    )?;
+
+    println!("🔑 No API keys found — minted a bootstrap admin key:");
+    println!("   {}", raw);
+    println!("   Save this now; it will not be shown again.");
+
+    Ok(())
+}
+
+fn lookup_key(conn: &rusqlite::Connection, raw_token: &str) -> rusqlite::Result<Option<ApiKeyContext>> {
+    let hash = hash_key(raw_token);
+
+    let mut stmt = conn.prepare("SELECT id, name, key_hash, scopes FROM api_keys WHERE revoked = 0")?;
+    let rows = stmt.query_map([], |row| {
+        let id: i64 = row.get(0)?;
+        let name: String = row.get(1)?;
+        let key_hash: String = row.get(2)?;
+        let scopes: String = row.get(3)?;
+        Ok((id, name, key_hash, scopes))
+    })?;
+
+    for row in rows {
+        let (id, name, key_hash, scopes) = row?;
+        if constant_time_eq(&key_hash, &hash) {
+            let scopes = scopes.split(',').filter_map(ApiKeyScope::from_str).collect();
+            return Ok(Some(ApiKeyContext {
+                key_id: id,
+                name,
+                scopes,
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Authenticates every request under the protected part of `/api`. Two
+/// credential kinds are accepted, since machine clients and a logged-in
+/// human share this same gate:
+///
+/// - an `Authorization: Bearer <api key>` checked against the hashed
+///   `api_keys` table, the original scheme this middleware enforced. On
+///   success an [`ApiKeyContext`] is attached so handlers can enforce
+///   their own scope with [`ApiKeyContext::require`].
+/// - a login token, either as `Authorization: Bearer <jwt>` or in the
+///   [`SESSION_COOKIE`] cookie set by
+///   `handlers::auth::login_handler`. On success an
+///   [`crate::api::jwt::AuthUser`] is attached instead.
+///
+/// A bearer value is tried as an API key first, since that lookup's
+/// `SqliteFailure`-free path is the cheaper one; it only falls through to
+/// JWT decoding if that lookup comes back empty. `auth_enabled = false`
+/// (local-dev mode) skips these checks and attaches an [`ApiKeyContext`]
+/// carrying every scope, so the server can be exposed on localhost without
+/// provisioning either an API key or a user account.
+pub async fn auth_middleware(
+    State(app_state): State<AppState>,
+    jar: CookieJar,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if !app_state.auth_enabled {
+        request.extensions_mut().insert(ApiKeyContext {
+            key_id: 0,
+            name: "local-dev".to_string(),
+            scopes: vec![ApiKeyScope::AgentsRead, ApiKeyScope::AgentsWrite, ApiKeyScope::KeysAdmin],
+        });
+        return Ok(next.run(request).await);
+    }
+
+    let bearer = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_string);
+
+    if let Some(token) = &bearer {
+        let context = {
+            let token = token.clone();
+            crate::api::db::with_conn(&app_state.db_pool, move |conn| lookup_key(conn, &token)).await?
+        };
+
+        if let Some(context) = context {
+            request.extensions_mut().insert(context);
+            return Ok(next.run(request).await);
+        }
+    }
+
+    let token = bearer.or_else(|| jar.get(SESSION_COOKIE).map(|c| c.value().to_string()));
+    if let Some(token) = token {
+        if let Ok(user) = decode_jwt(&token, app_state.jwt_secret.as_str()) {
+            request.extensions_mut().insert(user);
+            return Ok(next.run(request).await);
+        }
+    }
+
+    Err(ApiError::Unauthorized(
+        "missing or invalid credentials".to_string(),
+    ))
+}
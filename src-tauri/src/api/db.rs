@@ -0,0 +1,70 @@
+//! Pooled SQLite access for the HTTP API's handlers and background
+//! consumers.
+//!
+//! Previously every one of these sites held `agent_db.0.lock()` — a single
+//! `std::sync::Mutex<rusqlite::Connection>` — for the duration of its
+//! query, serializing all DB-touching requests behind one lock even though
+//! SQLite itself can serve multiple readers. This pools connections with
+//! `deadpool-sqlite` instead, and runs each query inside the pool's own
+//! `interact()` (which itself dispatches to `spawn_blocking`), so a slow
+//! query only blocks the one request holding that pooled connection.
+
+use std::path::Path;
+
+use crate::api::{ApiError, ApiResult};
+
+pub type DbPool = deadpool_sqlite::Pool;
+
+/// Default `--db-pool-size`, used when the flag isn't given.
+pub const DEFAULT_POOL_SIZE: usize = 8;
+
+/// Builds the pool `AppState::new` stores, backed by the same sqlite file
+/// `handle_api_mode` already opens directly to run its one-time table
+/// creation.
+pub fn create_pool(db_path: &Path, max_size: usize) -> ApiResult<DbPool> {
+    deadpool_sqlite::Config::new(db_path)
+        .builder(deadpool_sqlite::Runtime::Tokio1)
+        .map_err(|e| ApiError::Internal(format!("failed to configure database pool: {e}")))?
+        .max_size(max_size)
+        .build()
+        .map_err(|e| ApiError::Internal(format!("failed to build database pool: {e}")))
+}
+
+/// Runs a blocking rusqlite closure against a pooled connection, mapping
+/// every failure mode (pool exhaustion, a panicking closure, the query
+/// itself) onto [`ApiError::DatabaseError`] the same way the old
+/// `.0.lock().map_err(...)` call sites did.
+pub async fn with_conn<F, T>(pool: &DbPool, f: F) -> ApiResult<T>
+where
+    F: FnOnce(&rusqlite::Connection) -> rusqlite::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let conn = pool
+        .get()
+        .await
+        .map_err(|e| ApiError::DatabaseError(format!("failed to acquire pooled connection: {e}")))?;
+
+    conn.interact(move |conn| f(conn))
+        .await
+        .map_err(|e| ApiError::DatabaseError(format!("pooled connection task panicked: {e}")))?
+        .map_err(|e| ApiError::DatabaseError(e.to_string()))
+}
+
+/// Snapshot of the pool's saturation, surfaced by `health_check_handler`
+/// instead of just "did a lock acquire".
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
+pub struct PoolStatus {
+    pub max_size: usize,
+    pub in_use: usize,
+    pub available: usize,
+}
+
+pub fn pool_status(pool: &DbPool) -> PoolStatus {
+    let status = pool.status();
+    let available = status.available.max(0) as usize;
+    PoolStatus {
+        max_size: status.max_size,
+        in_use: status.size.saturating_sub(available),
+        available,
+    }
+}
@@ -0,0 +1,185 @@
+//! Generic runtime-failure reporting, independent of the agent-run-specific
+//! [`crate::api::errors::ErrorReporter`] (which only ever persists failures
+//! tied to a particular `agent_run_id`). Anything in the API server that
+//! notices it failed - a process spawn, a checkpoint operation, a database
+//! call - can report it here without knowing or caring whether a
+//! `GET /api/diagnostics` caller or `health_check_handler` is currently
+//! looking; both read back through [`DiagnosticsReporter::snapshot`] /
+//! [`DiagnosticsReporter::has_recent_errors`].
+//!
+//! The ring buffer lives in memory only (unlike `agent_run_errors`, which is
+//! persisted) - it's meant for "what's gone wrong recently", not a durable
+//! audit log, so it doesn't survive a restart.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+use utoipa::ToSchema;
+
+const CHANNEL_CAPACITY: usize = 256;
+const MAX_SINK_ATTEMPTS: u32 = 3;
+const RING_BUFFER_SIZE: usize = 100;
+/// How far back a report still counts toward `has_recent_errors` (and so
+/// toward `health_check_handler` reporting "degraded") - an error from an
+/// hour ago shouldn't keep the service looking unhealthy indefinitely.
+const RECENT_WINDOW: Duration = Duration::from_secs(300);
+
+/// A single runtime failure, reported by whatever call site noticed it.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Reportable {
+    /// Where this came from, e.g. `"process_spawn"`, `"checkpoint"`, `"db"`.
+    pub source: String,
+    pub message: String,
+    pub ts: String,
+}
+
+/// Response body for `GET /api/diagnostics`.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DiagnosticsSnapshot {
+    /// Oldest first, capped at [`RING_BUFFER_SIZE`].
+    pub recent_errors: Vec<Reportable>,
+    /// Total reports ever received, even ones the ring buffer has since
+    /// evicted - a counter that only grows for the life of the process.
+    pub total_count: u64,
+    /// How many of `recent_errors` fall within [`RECENT_WINDOW`] - the same
+    /// count `has_recent_errors` checks for non-zero.
+    pub recent_count: usize,
+}
+
+struct RingBuffer {
+    entries: VecDeque<Reportable>,
+    total_count: u64,
+}
+
+/// Handle used to report a [`Reportable`] without blocking the caller -
+/// logging, buffering, and sink delivery (including retries) all happen on
+/// the background task spawned by [`DiagnosticsReporter::spawn`].
+#[derive(Clone)]
+pub struct DiagnosticsReporter {
+    tx: mpsc::Sender<Reportable>,
+    buffer: Arc<Mutex<RingBuffer>>,
+}
+
+impl DiagnosticsReporter {
+    /// Spawns the long-lived consumer task and returns a handle failure
+    /// sites can clone and report through. `sink_url`, when set, is POSTed
+    /// a JSON-encoded `Reportable` for every report (e.g. a webhook an
+    /// operator wants paged on). Must be called from within a Tokio runtime.
+    pub fn spawn(sink_url: Option<String>) -> Self {
+        let buffer = Arc::new(Mutex::new(RingBuffer {
+            entries: VecDeque::with_capacity(RING_BUFFER_SIZE),
+            total_count: 0,
+        }));
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        tokio::spawn(run_consumer(rx, buffer.clone(), sink_url));
+        Self { tx, buffer }
+    }
+
+    /// Reports a failure. Never blocks: if the channel is full the report
+    /// is dropped and logged rather than stalling whatever just failed.
+    pub fn report(&self, source: impl Into<String>, message: impl Into<String>) {
+        let reportable = Reportable {
+            source: source.into(),
+            message: message.into(),
+            ts: chrono::Utc::now().to_rfc3339(),
+        };
+        if self.tx.try_send(reportable).is_err() {
+            log::warn!("diagnostics channel full or closed; dropping report");
+        }
+    }
+
+    pub async fn snapshot(&self) -> DiagnosticsSnapshot {
+        let buffer = self.buffer.lock().await;
+        let recent_count = buffer.entries.iter().filter(|e| is_recent(e)).count();
+        DiagnosticsSnapshot {
+            recent_errors: buffer.entries.iter().cloned().collect(),
+            total_count: buffer.total_count,
+            recent_count,
+        }
+    }
+
+    /// Whether at least one report has landed within [`RECENT_WINDOW`] -
+    /// folded into `health_check_handler`'s `overall_status`.
+    pub async fn has_recent_errors(&self) -> bool {
+        let buffer = self.buffer.lock().await;
+        buffer.entries.iter().any(is_recent)
+    }
+}
+
+fn is_recent(reportable: &Reportable) -> bool {
+    match chrono::DateTime::parse_from_rfc3339(&reportable.ts) {
+        Ok(ts) => {
+            let age = chrono::Utc::now().signed_duration_since(ts.with_timezone(&chrono::Utc));
+            age.num_seconds() >= 0 && (age.num_seconds() as u64) < RECENT_WINDOW.as_secs()
+        }
+        Err(_) => false,
+    }
+}
+
+async fn run_consumer(
+    mut rx: mpsc::Receiver<Reportable>,
+    buffer: Arc<Mutex<RingBuffer>>,
+    sink_url: Option<String>,
+) {
+    let client = sink_url.as_ref().map(|_| reqwest::Client::new());
+
+    while let Some(reportable) = rx.recv().await {
+        log::error!("[{}] {}", reportable.source, reportable.message);
+
+        {
+            let mut buffer = buffer.lock().await;
+            buffer.total_count += 1;
+            if buffer.entries.len() >= RING_BUFFER_SIZE {
+                buffer.entries.pop_front();
+            }
+            buffer.entries.push_back(reportable.clone());
+        }
+
+        if let (Some(url), Some(client)) = (&sink_url, &client) {
+            deliver_with_retry(client, url, &reportable).await;
+        }
+    }
+}
+
+async fn deliver_with_retry(client: &reqwest::Client, url: &str, reportable: &Reportable) {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+
+        let result = client.post(url).json(reportable).send().await;
+        let delivered = matches!(&result, Ok(resp) if resp.status().is_success());
+        if delivered {
+            return;
+        }
+
+        let description = match result {
+            Ok(resp) => format!("HTTP {}", resp.status()),
+            Err(e) => e.to_string(),
+        };
+
+        if attempt >= MAX_SINK_ATTEMPTS {
+            log::error!(
+                "Giving up delivering diagnostics report ({}) to {} after {} attempts: {}",
+                reportable.source,
+                url,
+                MAX_SINK_ATTEMPTS,
+                description
+            );
+            return;
+        }
+
+        let backoff_ms = 200u64 * (1 << (attempt - 1));
+        log::warn!(
+            "Diagnostics sink delivery ({}) to {} failed (attempt {}/{}): {} - retrying in {}ms",
+            reportable.source,
+            url,
+            attempt,
+            MAX_SINK_ATTEMPTS,
+            description,
+            backoff_ms
+        );
+        tokio::time::sleep(Duration::from_millis(backoff_ms)).await;
+    }
+}
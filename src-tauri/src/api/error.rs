@@ -1,10 +1,26 @@
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
+use serde::Serialize;
 use serde_json::json;
 use std::fmt;
+use utoipa::ToSchema;
 
 pub type ApiResult<T> = Result<T, ApiError>;
 
+/// The shape every [`ApiError`] is rendered as, documented once here so
+/// every handler's error responses share one schema in the generated
+/// OpenAPI spec. `code` is a stable, machine-readable string (e.g.
+/// `NOT_FOUND`); `details` is reserved for variant-specific context and is
+/// `null` today.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiErrorBody {
+    pub error: bool,
+    pub code: String,
+    pub message: String,
+    pub details: Option<serde_json::Value>,
+    pub status: u16,
+}
+
 #[derive(Debug)]
 pub enum ApiError {
     Internal(String),
@@ -36,8 +52,28 @@ impl fmt::Display for ApiError {
 
 impl std::error::Error for ApiError {}
 
+impl ApiError {
+    /// A stable, machine-readable identifier for this variant, independent
+    /// of the human-readable message, so clients can switch on error type
+    /// without parsing prose.
+    fn code(&self) -> &'static str {
+        match self {
+            ApiError::Internal(_) => "INTERNAL_ERROR",
+            ApiError::NotFound(_) => "NOT_FOUND",
+            ApiError::BadRequest(_) => "BAD_REQUEST",
+            ApiError::Unauthorized(_) => "UNAUTHORIZED",
+            ApiError::Forbidden(_) => "FORBIDDEN",
+            ApiError::Conflict(_) => "CONFLICT",
+            ApiError::ValidationError(_) => "VALIDATION_ERROR",
+            ApiError::DatabaseError(_) => "DATABASE_ERROR",
+            ApiError::ExternalServiceError(_) => "EXTERNAL_SERVICE_ERROR",
+        }
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
+        let code = self.code();
         let (status, error_message) = match self {
             ApiError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
             ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
@@ -52,7 +88,9 @@ impl IntoResponse for ApiError {
 
         let body = json!({
             "error": true,
+            "code": code,
             "message": error_message,
+            "details": null,
             "status": status.as_u16()
         });
 
@@ -0,0 +1,170 @@
+//! Durable record of *why* an agent run failed, beyond the terminal status
+//! flip on `agent_runs.status`. `ErrorReporter::report` is meant to be
+//! called from the run's failure sites — `execute_agent`, `kill_agent_session`,
+//! and process-registry cleanup in `commands/agents.rs` / `process/` — but
+//! those modules aren't part of this checkout (only `main.rs` and `api/`
+//! are present here), so this module only wires the reporting channel,
+//! storage, and read-side handler; the actual `report()` calls at those
+//! sites are left for whoever lands `commands/agents.rs`. Those call sites
+//! should read the [`RequestId`](crate::api::middleware::RequestId) out of
+//! the failing request's extensions and pass it through, so a `RunError`
+//! can be traced back to the `x-request-id` that triggered it.
+//!
+//! **Status: partial.** `ErrorReporter::report` has no caller anywhere in
+//! this checkout as of this module landing - `GET /api/agents/runs/{id}/errors`
+//! will return an empty list for every run until one of the sites above is
+//! wired in. Every `RunError` genuinely needs a concrete `agent_run_id` to
+//! attach to, and nothing in this checkout ever creates or transitions an
+//! `agent_runs` row (`execute_agent_handler` is a stub that always returns
+//! an error without touching the table), so there's no real failure here to report without
+//! fabricating one - unlike [`crate::api::diagnostics::DiagnosticsReporter`],
+//! which that same stub does legitimately report through.
+
+use serde::Serialize;
+use tokio::sync::mpsc;
+use utoipa::ToSchema;
+
+use crate::api::db::DbPool;
+
+const CHANNEL_CAPACITY: usize = 256;
+const MAX_WRITE_ATTEMPTS: u32 = 3;
+
+/// A single failure recorded against an agent run.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RunError {
+    pub agent_run_id: i64,
+    /// Where in the run's lifecycle this happened, e.g. `"execute"`,
+    /// `"kill"`, `"process_cleanup"`.
+    pub stage: String,
+    pub message: String,
+    pub ts: String,
+    /// The `x-request-id` of the HTTP call that triggered this failure, if
+    /// it happened inline with one (background process-registry cleanup has
+    /// none to attach), so the failure can be traced back to the request
+    /// from the edge through agent execution.
+    pub request_id: Option<String>,
+}
+
+impl RunError {
+    /// Build a report stamped with the current time, pulling `request_id`
+    /// from the failing request's [`RequestId`](crate::api::middleware::RequestId)
+    /// extension where the failure site has one (background cleanup that
+    /// isn't running inline with an HTTP call has none).
+    pub fn new(agent_run_id: i64, stage: impl Into<String>, message: impl Into<String>, request_id: Option<String>) -> Self {
+        Self {
+            agent_run_id,
+            stage: stage.into(),
+            message: message.into(),
+            ts: chrono::Utc::now().to_rfc3339(),
+            request_id,
+        }
+    }
+}
+
+/// Creates the `agent_run_errors` table if it doesn't exist yet. Called
+/// alongside the other manual table creation in `handle_api_mode`.
+pub fn ensure_agent_run_errors_table(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_run_errors (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            agent_run_id INTEGER NOT NULL,
+            stage TEXT NOT NULL,
+            message TEXT NOT NULL,
+            ts TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            request_id TEXT
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Handle failure sites use to report a `RunError` without blocking on the
+/// database — the send only fails if the bounded channel is full, in which
+/// case the report is dropped rather than stalling whatever just failed.
+#[derive(Clone)]
+pub struct ErrorReporter {
+    tx: mpsc::Sender<RunError>,
+}
+
+impl ErrorReporter {
+    /// Spawns the long-lived consumer task that owns the receiver and
+    /// persists every reported error, then returns a handle failure sites
+    /// can clone and report through. Must be called from within a Tokio
+    /// runtime.
+    pub fn spawn(db_pool: DbPool) -> Self {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        tokio::spawn(run_consumer(db_pool, rx));
+        Self { tx }
+    }
+
+    pub fn report(&self, error: RunError) {
+        if self.tx.try_send(error).is_err() {
+            log::warn!("agent_run_errors channel full or closed; dropping report");
+        }
+    }
+}
+
+async fn run_consumer(db_pool: DbPool, mut rx: mpsc::Receiver<RunError>) {
+    while let Some(error) = rx.recv().await {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let written = crate::api::db::with_conn(&db_pool, {
+                let error = error.clone();
+                move |conn| {
+                    conn.execute(
+                        "INSERT INTO agent_run_errors (agent_run_id, stage, message, ts, request_id) VALUES (?1, ?2, ?3, ?4, ?5)",
+                        rusqlite::params![error.agent_run_id, error.stage, error.message, error.ts, error.request_id],
+                    )
+                    .map(|_| ())
+                }
+            })
+            .await
+            .map_err(|e| e.to_string());
+
+            match written {
+                Ok(()) => break,
+                Err(e) if attempt < MAX_WRITE_ATTEMPTS => {
+                    let backoff_ms = 50u64 * (1 << (attempt - 1));
+                    log::warn!(
+                        "Failed to persist agent_run_errors entry (attempt {}/{}): {} - retrying in {}ms",
+                        attempt,
+                        MAX_WRITE_ATTEMPTS,
+                        e,
+                        backoff_ms
+                    );
+                    tokio::time::sleep(std::time::Duration::from_millis(backoff_ms)).await;
+                }
+                Err(e) => {
+                    log::error!(
+                        "Dropping agent_run_errors entry for run {} after {} attempts: {}",
+                        error.agent_run_id,
+                        MAX_WRITE_ATTEMPTS,
+                        e
+                    );
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Reads back the errors recorded for one agent run, oldest first, for the
+/// `GET /api/agents/runs/{id}/errors` handler.
+pub fn get_run_errors(conn: &rusqlite::Connection, agent_run_id: i64) -> rusqlite::Result<Vec<RunError>> {
+    let mut stmt = conn.prepare(
+        "SELECT agent_run_id, stage, message, ts, request_id FROM agent_run_errors WHERE agent_run_id = ?1 ORDER BY id ASC",
+    )?;
+
+    let rows = stmt.query_map(rusqlite::params![agent_run_id], |row| {
+        Ok(RunError {
+            agent_run_id: row.get(0)?,
+            stage: row.get(1)?,
+            message: row.get(2)?,
+            ts: row.get(3)?,
+            request_id: row.get(4)?,
+        })
+    })?;
+
+    rows.collect()
+}
@@ -0,0 +1,46 @@
+use axum::body::Bytes;
+use axum::http::{header::CONTENT_TYPE, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use rust_embed::RustEmbed;
+
+/// The compiled frontend, embedded into the binary so `opcode api` can
+/// serve the whole app over one port instead of needing a Tauri shell (or
+/// a separately hosted static site) in front of it.
+///
+/// `folder` is relative to this crate's `Cargo.toml` (`src-tauri/`), the
+/// same `../dist` the Tauri config points `frontendDist` at - run the
+/// frontend package's `npm run build` first; `cargo build` fails here
+/// without that directory present, the same way it would fail without a
+/// `src-tauri/icons/` directory for `tauri-build`.
+#[derive(RustEmbed)]
+#[folder = "../dist"]
+struct Frontend;
+
+/// Serves `uri`'s path out of the embedded frontend build, falling back to
+/// `index.html` for anything not found so client-side routes (e.g.
+/// `/projects/123`) resolve to the SPA shell instead of a 404. Registered
+/// with [`axum::Router::fallback`] after the API, docs, and `/metrics`
+/// routes are merged, so none of those are ever shadowed by it - an
+/// explicit route always wins over a fallback regardless of merge order,
+/// but mirroring that ordering here keeps the router readable.
+pub async fn serve_frontend(uri: Uri) -> Response {
+    let path = uri.path().trim_start_matches('/');
+
+    if let Some(asset) = Frontend::get(path) {
+        return asset_response(path, asset.data);
+    }
+
+    match Frontend::get("index.html") {
+        Some(index) => asset_response("index.html", index.data),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+fn asset_response(path: &str, data: std::borrow::Cow<'static, [u8]>) -> Response {
+    let mime = mime_guess::from_path(path).first_or_text_html();
+    (
+        [(CONTENT_TYPE, mime.as_ref().to_string())],
+        Bytes::from(data.into_owned()),
+    )
+        .into_response()
+}
@@ -1,11 +1,21 @@
-use crate::api::{ApiError, ApiResult, AppState};
+use crate::api::auth::{ApiKeyContext, ApiKeyScope};
+use crate::api::errors::RunError;
+use crate::api::streaming::RunEvent;
+use crate::api::{ApiError, ApiErrorBody, ApiResult, AppState};
 use crate::api::response::{success, success_with_message, success_message};
 use crate::commands::agents::{Agent, AgentRun, AgentRunWithMetrics};
 use axum::extract::{Path, Query, State};
-use axum::http::StatusCode;
-use axum::{Json, response::{IntoResponse, Response}};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::{Extension, Json, response::{IntoResponse, Response}};
+use futures::stream::{self, StreamExt};
+use rusqlite::OptionalExtension;
 use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio_stream::{wrappers::BroadcastStream, Stream};
 use utoipa::ToSchema;
 
 #[derive(Debug, Deserialize, ToSchema)]
@@ -40,25 +50,22 @@ pub struct AgentQueryParams {
     path = "/api/agents",
     responses(
         (status = 200, description = "List of agents", body = [Agent]),
-        (status = 500, description = "Internal server error")
+        (status = 500, description = "Internal server error", body = ApiErrorBody)
     ),
     tag = "agents"
 )]
 pub async fn list_agents_handler(
     State(app_state): State<AppState>,
+    Extension(ctx): Extension<ApiKeyContext>,
     Query(params): Query<AgentQueryParams>,
 ) -> ApiResult<Json<crate::api::ApiResponse<Vec<Agent>>>> {
-    let db = &app_state.agent_db;
-    
-    // Direct database access instead of using Tauri commands
-    let conn = db.0.lock().map_err(|e| ApiError::DatabaseError(e.to_string()))?;
-    
-    let mut stmt = conn
-        .prepare("SELECT id, name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks, created_at, updated_at FROM agents ORDER BY created_at DESC")
-        .map_err(|e| ApiError::DatabaseError(e.to_string()))?;
+    ctx.require(ApiKeyScope::AgentsRead)?;
 
-    let agents = stmt
-        .query_map([], |row| {
+    let agents: Vec<Agent> = crate::api::db::with_conn(&app_state.db_pool, |conn| {
+        let mut stmt = conn
+            .prepare("SELECT id, name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks, created_at, updated_at FROM agents ORDER BY created_at DESC")?;
+
+        stmt.query_map([], |row| {
             Ok(Agent {
                 id: Some(row.get(0)?),
                 name: row.get(1)?,
@@ -73,11 +80,11 @@ pub async fn list_agents_handler(
                 created_at: row.get(10)?,
                 updated_at: row.get(11)?,
             })
-        })
-        .map_err(|e| ApiError::DatabaseError(e.to_string()))?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| ApiError::DatabaseError(e.to_string()))?;
-    
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+    })
+    .await?;
+
     // Apply pagination if requested
     let agents = if let (Some(page), Some(limit)) = (params.page, params.limit) {
         let start = (page.saturating_sub(1) * limit) as usize;
@@ -96,8 +103,8 @@ pub async fn list_agents_handler(
     path = "/api/agents/{id}",
     responses(
         (status = 200, description = "Agent details", body = Agent),
-        (status = 404, description = "Agent not found"),
-        (status = 500, description = "Internal server error")
+        (status = 404, description = "Agent not found", body = ApiErrorBody),
+        (status = 500, description = "Internal server error", body = ApiErrorBody)
     ),
     params(
         ("id" = i64, Path, description = "Agent ID")
@@ -106,13 +113,13 @@ pub async fn list_agents_handler(
 )]
 pub async fn get_agent_handler(
     State(app_state): State<AppState>,
+    Extension(ctx): Extension<ApiKeyContext>,
     Path(id): Path<i64>,
 ) -> ApiResult<Json<crate::api::ApiResponse<Agent>>> {
-    let db = &app_state.agent_db;
-    let conn = db.0.lock().map_err(|e| ApiError::DatabaseError(e.to_string()))?;
-    
-    let agent = conn
-        .query_row(
+    ctx.require(ApiKeyScope::AgentsRead)?;
+
+    let agent = crate::api::db::with_conn(&app_state.db_pool, move |conn| {
+        conn.query_row(
             "SELECT id, name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks, created_at, updated_at FROM agents WHERE id = ?1",
             rusqlite::params![id],
             |row| {
@@ -132,15 +139,11 @@ pub async fn get_agent_handler(
                 })
             },
         )
-        .map_err(|e| {
-            match e {
-                rusqlite::Error::QueryReturnedNoRows => {
-                    ApiError::NotFound(format!("Agent with ID {} not found", id))
-                }
-                _ => ApiError::DatabaseError(e.to_string())
-            }
-        })?;
-    
+        .optional()
+    })
+    .await?
+    .ok_or_else(|| ApiError::NotFound(format!("Agent with ID {} not found", id)))?;
+
     Ok(Json(success(agent)))
 }
 
@@ -151,34 +154,32 @@ pub async fn get_agent_handler(
     request_body = CreateAgentRequest,
     responses(
         (status = 201, description = "Agent created successfully", body = Agent),
-        (status = 400, description = "Invalid request data"),
-        (status = 500, description = "Internal server error")
+        (status = 400, description = "Invalid request data", body = ApiErrorBody),
+        (status = 500, description = "Internal server error", body = ApiErrorBody)
     ),
     tag = "agents"
 )]
 pub async fn create_agent_handler(
     State(app_state): State<AppState>,
+    Extension(ctx): Extension<ApiKeyContext>,
     Json(request): Json<CreateAgentRequest>,
 ) -> ApiResult<Response> {
-    let db = &app_state.agent_db;
-    let conn = db.0.lock().map_err(|e| ApiError::DatabaseError(e.to_string()))?;
-    
-    let model = request.model.unwrap_or_else(|| "sonnet".to_string());
+    ctx.require(ApiKeyScope::AgentsWrite)?;
+
+    let model = request.model.clone().unwrap_or_else(|| "sonnet".to_string());
     let enable_file_read = request.enable_file_read.unwrap_or(true);
     let enable_file_write = request.enable_file_write.unwrap_or(true);
     let enable_network = request.enable_network.unwrap_or(false);
 
-    conn.execute(
-        "INSERT INTO agents (name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-        rusqlite::params![request.name, request.icon, request.system_prompt, request.default_task, model, enable_file_read, enable_file_write, enable_network, request.hooks],
-    )
-    .map_err(|e| ApiError::DatabaseError(e.to_string()))?;
+    let agent = crate::api::db::with_conn(&app_state.db_pool, move |conn| {
+        conn.execute(
+            "INSERT INTO agents (name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            rusqlite::params![request.name, request.icon, request.system_prompt, request.default_task, model, enable_file_read, enable_file_write, enable_network, request.hooks],
+        )?;
 
-    let id = conn.last_insert_rowid();
+        let id = conn.last_insert_rowid();
 
-    // Fetch the created agent
-    let agent = conn
-        .query_row(
+        conn.query_row(
             "SELECT id, name, icon, system_prompt, default_task, model, enable_file_read, enable_file_write, enable_network, hooks, created_at, updated_at FROM agents WHERE id = ?1",
             rusqlite::params![id],
             |row| {
@@ -198,8 +199,9 @@ pub async fn create_agent_handler(
                 })
             },
         )
-        .map_err(|e| ApiError::DatabaseError(e.to_string()))?;
-    
+    })
+    .await?;
+
     let response = success_with_message(agent, "Agent created successfully".to_string());
     Ok((StatusCode::CREATED, Json(response)).into_response())
 }
@@ -210,8 +212,8 @@ pub async fn create_agent_handler(
     path = "/api/agents/{id}/execute",
     request_body = ExecuteAgentRequest,
     responses(
-        (status = 501, description = "Not implemented - use desktop application for agent execution"),
-        (status = 404, description = "Agent not found"),
+        (status = 501, description = "Not implemented - use desktop application for agent execution", body = ApiErrorBody),
+        (status = 404, description = "Agent not found", body = ApiErrorBody),
     ),
     params(
         ("id" = i64, Path, description = "Agent ID")
@@ -219,10 +221,25 @@ pub async fn create_agent_handler(
     tag = "agents"
 )]
 pub async fn execute_agent_handler(
-    State(_app_state): State<AppState>,
-    Path(_agent_id): Path<i64>,
+    State(app_state): State<AppState>,
+    Extension(ctx): Extension<ApiKeyContext>,
+    Path(agent_id): Path<i64>,
     Json(_request): Json<ExecuteAgentRequest>,
 ) -> ApiResult<Response> {
+    ctx.require(ApiKeyScope::AgentsWrite)?;
+
+    // The one real call site in this checkout for `DiagnosticsReporter` -
+    // `commands/agents.rs`'s actual execute/kill/process-cleanup sites
+    // aren't part of it (see the module doc on `api::errors`), but a
+    // client hitting an endpoint this API can't yet serve is itself a
+    // runtime condition worth surfacing through `GET /api/diagnostics`
+    // and `health_check_handler`, exercising the same reporting path
+    // those sites would use once they land.
+    app_state.diagnostics.report(
+        "execute_agent",
+        format!("Agent execution via HTTP API is not yet implemented (agent_id={agent_id})"),
+    );
+
     Err(ApiError::Internal(
         "Agent execution via HTTP API is not yet implemented. Please use the desktop application.".to_string()
     ))
@@ -234,8 +251,8 @@ pub async fn execute_agent_handler(
     path = "/api/agents/{id}/runs",
     responses(
         (status = 200, description = "List of agent runs", body = [AgentRun]),
-        (status = 404, description = "Agent not found"),
-        (status = 500, description = "Internal server error")
+        (status = 404, description = "Agent not found", body = ApiErrorBody),
+        (status = 500, description = "Internal server error", body = ApiErrorBody)
     ),
     params(
         ("id" = i64, Path, description = "Agent ID")
@@ -244,18 +261,18 @@ pub async fn execute_agent_handler(
 )]
 pub async fn list_agent_runs_handler(
     State(app_state): State<AppState>,
+    Extension(ctx): Extension<ApiKeyContext>,
     Path(agent_id): Path<i64>,
 ) -> ApiResult<Json<crate::api::ApiResponse<Vec<AgentRun>>>> {
-    let db = &app_state.agent_db;
-    let conn = db.0.lock().map_err(|e| ApiError::DatabaseError(e.to_string()))?;
-    
-    let mut stmt = conn.prepare(
-        "SELECT id, agent_id, agent_name, agent_icon, task, model, project_path, session_id, status, pid, process_started_at, created_at, completed_at 
-         FROM agent_runs WHERE agent_id = ?1 ORDER BY created_at DESC"
-    ).map_err(|e| ApiError::DatabaseError(e.to_string()))?;
+    ctx.require(ApiKeyScope::AgentsRead)?;
 
-    let runs = stmt
-        .query_map(rusqlite::params![agent_id], |row| {
+    let runs: Vec<AgentRun> = crate::api::db::with_conn(&app_state.db_pool, move |conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, agent_id, agent_name, agent_icon, task, model, project_path, session_id, status, pid, process_started_at, created_at, completed_at
+             FROM agent_runs WHERE agent_id = ?1 ORDER BY created_at DESC"
+        )?;
+
+        stmt.query_map(rusqlite::params![agent_id], |row| {
             Ok(AgentRun {
                 id: Some(row.get(0)?),
                 agent_id: row.get(1)?,
@@ -271,11 +288,11 @@ pub async fn list_agent_runs_handler(
                 created_at: row.get(11)?,
                 completed_at: row.get(12)?,
             })
-        })
-        .map_err(|e| ApiError::DatabaseError(e.to_string()))?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| ApiError::DatabaseError(e.to_string()))?;
-    
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+    })
+    .await?;
+
     Ok(Json(success(runs)))
 }
 
@@ -285,23 +302,23 @@ pub async fn list_agent_runs_handler(
     path = "/api/agents/runs",
     responses(
         (status = 200, description = "List of all agent runs", body = [AgentRun]),
-        (status = 500, description = "Internal server error")
+        (status = 500, description = "Internal server error", body = ApiErrorBody)
     ),
     tag = "agents"
 )]
 pub async fn list_all_agent_runs_handler(
     State(app_state): State<AppState>,
+    Extension(ctx): Extension<ApiKeyContext>,
 ) -> ApiResult<Json<crate::api::ApiResponse<Vec<AgentRun>>>> {
-    let db = &app_state.agent_db;
-    let conn = db.0.lock().map_err(|e| ApiError::DatabaseError(e.to_string()))?;
-    
-    let mut stmt = conn.prepare(
-        "SELECT id, agent_id, agent_name, agent_icon, task, model, project_path, session_id, status, pid, process_started_at, created_at, completed_at 
-         FROM agent_runs ORDER BY created_at DESC"
-    ).map_err(|e| ApiError::DatabaseError(e.to_string()))?;
+    ctx.require(ApiKeyScope::AgentsRead)?;
 
-    let runs = stmt
-        .query_map([], |row| {
+    let runs: Vec<AgentRun> = crate::api::db::with_conn(&app_state.db_pool, |conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, agent_id, agent_name, agent_icon, task, model, project_path, session_id, status, pid, process_started_at, created_at, completed_at
+             FROM agent_runs ORDER BY created_at DESC"
+        )?;
+
+        stmt.query_map([], |row| {
             Ok(AgentRun {
                 id: Some(row.get(0)?),
                 agent_id: row.get(1)?,
@@ -317,10 +334,145 @@ pub async fn list_all_agent_runs_handler(
                 created_at: row.get(11)?,
                 completed_at: row.get(12)?,
             })
-        })
-        .map_err(|e| ApiError::DatabaseError(e.to_string()))?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| ApiError::DatabaseError(e.to_string()))?;
-    
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+    })
+    .await?;
+
     Ok(Json(success(runs)))
-}
\ No newline at end of file
+}
+/// Turns one [`RunEvent`] into the SSE wire format, tagging it with its
+/// `seq` as the event id so a reconnecting client's `Last-Event-ID` lines up
+/// with [`OutputBroadcaster::subscribe`]'s replay filter.
+pub(crate) fn run_event_to_sse(event: RunEvent) -> Event {
+    match event {
+        RunEvent::Line { seq, text } => Event::default()
+            .id(seq.to_string())
+            .event("line")
+            .data(text),
+        RunEvent::Done { seq, exit_code } => Event::default()
+            .id(seq.to_string())
+            .event("done")
+            .data(
+                serde_json::json!({ "exit_code": exit_code }).to_string(),
+            ),
+    }
+}
+
+/// Parses the standard `Last-Event-ID` reconnection header, if present.
+pub(crate) fn last_event_id(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+}
+
+/// Streams a running (or already-finished) agent run's output as Server-Sent
+/// Events.
+///
+/// Output only flows here once something calls `OutputBroadcaster`'s
+/// `publish_line`/`publish_done` for this run id - today nothing does,
+/// since `execute_agent_handler` itself always returns an error and
+/// there's no process supervisor in this checkout wiring a spawned Claude
+/// process's stdout back into the broadcaster. A client connecting to a run
+/// that's already in a terminal `status` (and whose tail buffer has already
+/// rolled past any `done` event, or never had a live producer at all) gets a
+/// synthetic `done` event built from the stored row instead of hanging
+/// forever waiting for one that will never arrive.
+#[utoipa::path(
+    get,
+    path = "/api/agents/runs/{id}/stream",
+    responses(
+        (status = 200, description = "SSE stream of run output", content_type = "text/event-stream"),
+        (status = 404, description = "Run not found", body = ApiErrorBody),
+    ),
+    params(
+        ("id" = i64, Path, description = "Agent run ID"),
+        ("Last-Event-ID" = Option<u64>, Header, description = "Resume after this event sequence number")
+    ),
+    tag = "agents"
+)]
+pub async fn stream_agent_run_handler(
+    State(app_state): State<AppState>,
+    Extension(ctx): Extension<ApiKeyContext>,
+    Path(run_id): Path<i64>,
+    headers: HeaderMap,
+) -> ApiResult<Sse<Pin<Box<dyn Stream<Item = Result<Event, Infallible>> + Send>>>> {
+    ctx.require(ApiKeyScope::AgentsRead)?;
+
+    let status = crate::api::db::with_conn(&app_state.db_pool, move |conn| {
+        conn.query_row(
+            "SELECT status FROM agent_runs WHERE id = ?1",
+            rusqlite::params![run_id],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+    })
+    .await?
+    .ok_or_else(|| ApiError::NotFound(format!("Agent run with ID {} not found", run_id)))?;
+
+    let run_key = run_id.to_string();
+    let (replay, rx) = app_state
+        .output_broadcaster
+        .subscribe(&run_key, last_event_id(&headers));
+
+    let already_done = replay.iter().any(|e| matches!(e, RunEvent::Done { .. }));
+    let is_terminal = matches!(status.as_str(), "completed" | "failed" | "cancelled");
+
+    let replay_stream = stream::iter(replay.into_iter().map(|e| Ok(run_event_to_sse(e))));
+    let live_stream = BroadcastStream::new(rx).filter_map(|item| async move {
+        match item {
+            Ok(event) => Some(Ok(run_event_to_sse(event))),
+            // A slow subscriber that fell behind the broadcast channel's
+            // buffer - drop the gap rather than erroring the whole stream.
+            Err(tokio_stream::wrappers::errors::BroadcastStreamRecvError::Lagged(_)) => None,
+        }
+    });
+
+    let combined: Pin<Box<dyn Stream<Item = Result<Event, Infallible>> + Send>> =
+        if is_terminal && !already_done {
+            let synthetic = stream::once(async move {
+                Ok(Event::default()
+                    .event("done")
+                    .data(serde_json::json!({ "exit_code": null }).to_string()))
+            });
+            Box::pin(replay_stream.chain(synthetic))
+        } else {
+            Box::pin(replay_stream.chain(live_stream))
+        };
+
+    Ok(Sse::new(combined).keep_alive(
+        KeepAlive::new()
+            .interval(Duration::from_secs(15))
+            .text("heartbeat"),
+    ))
+}
+
+/// List the errors recorded for a specific agent run, for diagnosing why
+/// its status became `Failed`.
+#[utoipa::path(
+    get,
+    path = "/api/agents/runs/{id}/errors",
+    responses(
+        (status = 200, description = "List of errors recorded for the run", body = [RunError]),
+        (status = 500, description = "Internal server error", body = ApiErrorBody)
+    ),
+    params(
+        ("id" = i64, Path, description = "Agent run ID")
+    ),
+    tag = "agents"
+)]
+pub async fn get_run_errors_handler(
+    State(app_state): State<AppState>,
+    Extension(ctx): Extension<ApiKeyContext>,
+    Path(run_id): Path<i64>,
+) -> ApiResult<Json<crate::api::ApiResponse<Vec<RunError>>>> {
+    ctx.require(ApiKeyScope::AgentsRead)?;
+
+    let errors = crate::api::db::with_conn(&app_state.db_pool, move |conn| {
+        crate::api::errors::get_run_errors(conn, run_id)
+    })
+    .await?;
+
+    Ok(Json(success(errors)))
+}
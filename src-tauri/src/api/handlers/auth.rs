@@ -0,0 +1,157 @@
+use crate::api::auth::{mint_api_key, revoke_api_key, AdminContext, ApiKeyScope, SESSION_COOKIE};
+use crate::api::jwt::encode_jwt;
+use crate::api::response::success;
+use crate::api::users::verify_user_credentials;
+use crate::api::{ApiError, ApiErrorBody, ApiResult, AppState};
+use axum::extract::{Path, State};
+use axum::Json;
+use axum_extra::extract::cookie::{Cookie, SameSite};
+use axum_extra::extract::CookieJar;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MintApiKeyRequest {
+    pub name: String,
+    /// e.g. `["agents:read", "agents:write"]`. Unrecognized scopes are
+    /// silently ignored rather than rejected.
+    pub scopes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MintApiKeyResponse {
+    pub id: i64,
+    pub key: String,
+}
+
+fn parse_scopes(raw: &[String]) -> Vec<ApiKeyScope> {
+    raw.iter()
+        .filter_map(|s| match s.as_str() {
+            "agents:read" => Some(ApiKeyScope::AgentsRead),
+            "agents:write" => Some(ApiKeyScope::AgentsWrite),
+            "keys:admin" => Some(ApiKeyScope::KeysAdmin),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Mint a new API key. Requires `keys:admin` when called with an API key,
+/// or just a logged-in session, since login accounts have no scopes to
+/// check — see [`AdminContext::require_admin`].
+#[utoipa::path(
+    post,
+    path = "/api/auth/keys",
+    request_body = MintApiKeyRequest,
+    responses(
+        (status = 200, description = "Newly minted API key (shown once)", body = MintApiKeyResponse),
+        (status = 403, description = "Caller lacks keys:admin scope", body = ApiErrorBody),
+        (status = 500, description = "Internal server error", body = ApiErrorBody)
+    ),
+    tag = "auth"
+)]
+pub async fn mint_api_key_handler(
+    State(app_state): State<AppState>,
+    ctx: AdminContext,
+    Json(payload): Json<MintApiKeyRequest>,
+) -> ApiResult<Json<crate::api::ApiResponse<MintApiKeyResponse>>> {
+    ctx.require_admin()?;
+
+    if payload.name.trim().is_empty() {
+        return Err(ApiError::ValidationError("name cannot be empty".to_string()));
+    }
+
+    let scopes = parse_scopes(&payload.scopes);
+    let name = payload.name.clone();
+    let (id, key) = crate::api::db::with_conn(&app_state.db_pool, move |conn| {
+        mint_api_key(conn, &name, &scopes)
+    })
+    .await?;
+
+    Ok(Json(success(MintApiKeyResponse { id, key })))
+}
+
+/// Revoke an API key by id. Requires `keys:admin` when called with an API
+/// key, or just a logged-in session — see [`AdminContext::require_admin`].
+#[utoipa::path(
+    delete,
+    path = "/api/auth/keys/{id}",
+    params(("id" = i64, Path, description = "API key id")),
+    responses(
+        (status = 200, description = "Key revoked"),
+        (status = 403, description = "Caller lacks keys:admin scope", body = ApiErrorBody),
+        (status = 404, description = "No key with that id", body = ApiErrorBody),
+        (status = 500, description = "Internal server error", body = ApiErrorBody)
+    ),
+    tag = "auth"
+)]
+pub async fn revoke_api_key_handler(
+    State(app_state): State<AppState>,
+    ctx: AdminContext,
+    Path(id): Path<i64>,
+) -> ApiResult<Json<crate::api::ApiResponse<()>>> {
+    ctx.require_admin()?;
+
+    let revoked = crate::api::db::with_conn(&app_state.db_pool, move |conn| revoke_api_key(conn, id)).await?;
+    if !revoked {
+        return Err(ApiError::NotFound(format!("No API key with id {}", id)));
+    }
+
+    Ok(Json(success(())))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LoginResponse {
+    /// Also handed back in the body (not just the cookie) so a non-browser
+    /// client can use it as an `Authorization: Bearer` token instead.
+    pub token: String,
+    pub username: String,
+}
+
+/// Log in with a username/password and receive a login token, both in the
+/// response body and as an HttpOnly cookie. Left unauthenticated, unlike
+/// `/api/auth/keys` - there's no token yet for `auth_middleware` to have
+/// checked.
+#[utoipa::path(
+    post,
+    path = "/api/auth/login",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Login succeeded", body = LoginResponse),
+        (status = 401, description = "Unknown username or wrong password", body = ApiErrorBody),
+        (status = 500, description = "Internal server error", body = ApiErrorBody)
+    ),
+    tag = "auth"
+)]
+pub async fn login_handler(
+    State(app_state): State<AppState>,
+    jar: CookieJar,
+    Json(payload): Json<LoginRequest>,
+) -> ApiResult<(CookieJar, Json<crate::api::ApiResponse<LoginResponse>>)> {
+    let username = payload.username.clone();
+    let password = payload.password.clone();
+    let user = crate::api::db::with_conn(&app_state.db_pool, move |conn| {
+        verify_user_credentials(conn, &username, &password)
+    })
+    .await?;
+
+    let user = user.ok_or_else(|| ApiError::Unauthorized("invalid username or password".to_string()))?;
+    let token = encode_jwt(user.id, &user.username, app_state.jwt_secret.as_str())?;
+
+    let cookie = Cookie::build((SESSION_COOKIE, token.clone()))
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Lax)
+        .path("/")
+        .build();
+
+    Ok((
+        jar.add(cookie),
+        Json(success(LoginResponse { token, username: user.username })),
+    ))
+}
@@ -0,0 +1,27 @@
+use crate::api::auth::{ApiKeyContext, ApiKeyScope};
+use crate::api::diagnostics::DiagnosticsSnapshot;
+use crate::api::response::success;
+use crate::api::{ApiResult, AppState};
+use axum::extract::State;
+use axum::{Extension, Json};
+
+/// Recent runtime failures and counts reported through
+/// [`crate::api::diagnostics::DiagnosticsReporter`] - the same data
+/// `health_check_handler` checks to decide whether to report "degraded".
+#[utoipa::path(
+    get,
+    path = "/api/diagnostics",
+    responses(
+        (status = 200, description = "Recent runtime errors and counts", body = DiagnosticsSnapshot),
+    ),
+    tag = "diagnostics"
+)]
+pub async fn get_diagnostics_handler(
+    State(app_state): State<AppState>,
+    Extension(ctx): Extension<ApiKeyContext>,
+) -> ApiResult<Json<crate::api::ApiResponse<DiagnosticsSnapshot>>> {
+    ctx.require(ApiKeyScope::AgentsRead)?;
+
+    let snapshot = app_state.diagnostics.snapshot().await;
+    Ok(Json(success(snapshot)))
+}
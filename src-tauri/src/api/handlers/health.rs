@@ -1,4 +1,5 @@
-use crate::api::{ApiError, ApiResult, AppState};
+use crate::api::db::PoolStatus;
+use crate::api::{ApiResult, AppState};
 use crate::api::response::success;
 use axum::extract::{Query, State};
 use axum::Json;
@@ -11,6 +12,7 @@ pub struct HealthResponse {
     pub version: String,
     pub timestamp: String,
     pub services: ServiceStatus,
+    pub pool: PoolStatus,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -32,11 +34,20 @@ pub struct ServiceStatus {
 pub async fn health_check_handler(
     State(app_state): State<AppState>,
 ) -> ApiResult<Json<crate::api::ApiResponse<HealthResponse>>> {
-    // Check database connectivity
-    let db_status = match app_state.agent_db.0.lock() {
+    // Check database connectivity by actually round-tripping a trivial
+    // query through the pool, rather than just confirming a lock acquires
+    // (there's no lock to acquire anymore - a pooled connection could still
+    // fail to check out if every slot is stuck on a wedged query).
+    let db_status = match crate::api::db::with_conn(&app_state.db_pool, |conn| {
+        conn.query_row("SELECT 1", [], |row| row.get::<_, i64>(0))
+    })
+    .await
+    {
         Ok(_) => "healthy".to_string(),
         Err(_) => "unhealthy".to_string(),
     };
+
+    let pool_status = crate::api::db::pool_status(&app_state.db_pool);
     
     // Check checkpoint manager
     let checkpoint_status = {
@@ -54,14 +65,28 @@ pub async fn health_check_handler(
         Err(_) => "unhealthy".to_string(),
     };
     
-    let overall_status = if db_status == "healthy" 
-        && checkpoint_status == "healthy" 
-        && process_status == "healthy" {
+    // Folds in whether `DiagnosticsReporter` has seen any runtime failure
+    // recently, not just whether the three live connectivity probes above
+    // currently succeed - a process that's actively failing agent runs
+    // should read "degraded" even while the DB/checkpoint/process checks
+    // themselves all still pass.
+    let has_recent_errors = app_state.diagnostics.has_recent_errors().await;
+
+    let overall_status = if db_status == "healthy"
+        && checkpoint_status == "healthy"
+        && process_status == "healthy"
+        && !has_recent_errors {
         "healthy"
     } else {
         "degraded"
     };
-    
+
+    crate::api::metrics::record_service_gauges(
+        db_status == "healthy",
+        checkpoint_status == "healthy",
+        process_status == "healthy",
+    );
+
     let health = HealthResponse {
         status: overall_status.to_string(),
         version: env!("CARGO_PKG_VERSION").to_string(),
@@ -71,6 +96,7 @@ pub async fn health_check_handler(
             checkpoint_manager: checkpoint_status,
             process_registry: process_status,
         },
+        pool: pool_status,
     };
     
     Ok(Json(success(health)))
@@ -0,0 +1,17 @@
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+
+/// Renders the process's current Prometheus metrics. Left unauthenticated
+/// and outside `/api`, like `/health`, so a scraper can reach it without
+/// provisioning an API key.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    responses(
+        (status = 200, description = "Prometheus text-format metrics snapshot", content_type = "text/plain"),
+    ),
+    tag = "health"
+)]
+pub async fn metrics_handler() -> impl IntoResponse {
+    ([(CONTENT_TYPE, "text/plain; version=0.0.4")], crate::api::metrics::render())
+}
@@ -2,8 +2,14 @@ pub mod agents;
 pub mod projects;
 pub mod sessions;
 pub mod health;
+pub mod auth;
+pub mod metrics;
+pub mod diagnostics;
 
 pub use agents::*;
 pub use projects::*;
 pub use sessions::*;
-pub use health::*;
\ No newline at end of file
+pub use health::*;
+pub use auth::*;
+pub use metrics::*;
+pub use diagnostics::*;
\ No newline at end of file
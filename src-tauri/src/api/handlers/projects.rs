@@ -1,8 +1,9 @@
-use crate::api::{ApiError, ApiResult, AppState};
+use crate::api::auth::{ApiKeyContext, ApiKeyScope};
+use crate::api::{ApiError, ApiErrorBody, ApiResult, AppState};
 use crate::api::response::success;
 use crate::commands::claude::{Project, Session};
 use axum::extract::{Path, State};
-use axum::Json;
+use axum::{Extension, Json};
 use utoipa::ToSchema;
 
 /// List all projects
@@ -11,13 +12,16 @@ use utoipa::ToSchema;
     path = "/api/projects",
     responses(
         (status = 200, description = "List of projects", body = [Project]),
-        (status = 500, description = "Internal server error")
+        (status = 500, description = "Internal server error", body = ApiErrorBody)
     ),
     tag = "projects"
 )]
 pub async fn list_projects_handler(
     State(_app_state): State<AppState>,
+    Extension(ctx): Extension<ApiKeyContext>,
 ) -> ApiResult<Json<crate::api::ApiResponse<Vec<Project>>>> {
+    ctx.require(ApiKeyScope::AgentsRead)?;
+
     // Direct call to list_projects function (no Tauri dependency)
     let projects = crate::commands::claude::list_projects()
         .await
@@ -32,8 +36,8 @@ pub async fn list_projects_handler(
     path = "/api/projects/{project_id}/sessions",
     responses(
         (status = 200, description = "List of sessions for the project", body = [Session]),
-        (status = 404, description = "Project not found"),
-        (status = 500, description = "Internal server error")
+        (status = 404, description = "Project not found", body = ApiErrorBody),
+        (status = 500, description = "Internal server error", body = ApiErrorBody)
     ),
     params(
         ("project_id" = String, Path, description = "Project ID")
@@ -42,8 +46,11 @@ pub async fn list_projects_handler(
 )]
 pub async fn get_project_sessions_handler(
     State(_app_state): State<AppState>,
+    Extension(ctx): Extension<ApiKeyContext>,
     Path(project_id): Path<String>,
 ) -> ApiResult<Json<crate::api::ApiResponse<Vec<Session>>>> {
+    ctx.require(ApiKeyScope::AgentsRead)?;
+
     let sessions = crate::commands::claude::get_project_sessions(project_id.clone())
         .await
         .map_err(|e| {
@@ -1,8 +1,18 @@
-use crate::api::{ApiError, ApiResult, AppState};
+use crate::api::auth::{ApiKeyContext, ApiKeyScope};
+use crate::api::streaming::RunEvent;
+use crate::api::{ApiError, ApiErrorBody, ApiResult, AppState};
 use crate::api::response::success;
 use axum::extract::{Path, State};
-use axum::Json;
+use axum::http::HeaderMap;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::{Extension, Json};
+use futures::stream::{self, StreamExt};
+use rusqlite::OptionalExtension;
 use serde_json::Value;
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio_stream::{wrappers::BroadcastStream, Stream};
 use utoipa::ToSchema;
 
 /// Load session history
@@ -11,8 +21,8 @@ use utoipa::ToSchema;
     path = "/api/sessions/{session_id}/history/{project_id}",
     responses(
         (status = 200, description = "Session history messages", body = [Value]),
-        (status = 404, description = "Session not found"),
-        (status = 500, description = "Internal server error")
+        (status = 404, description = "Session not found", body = ApiErrorBody),
+        (status = 500, description = "Internal server error", body = ApiErrorBody)
     ),
     params(
         ("session_id" = String, Path, description = "Session ID"),
@@ -22,8 +32,11 @@ use utoipa::ToSchema;
 )]
 pub async fn get_session_history_handler(
     State(_app_state): State<AppState>,
+    Extension(ctx): Extension<ApiKeyContext>,
     Path((session_id, project_id)): Path<(String, String)>,
 ) -> ApiResult<Json<crate::api::ApiResponse<Vec<Value>>>> {
+    ctx.require(ApiKeyScope::AgentsRead)?;
+
     let history = crate::commands::claude::load_session_history(session_id.clone(), project_id.clone())
         .await
         .map_err(|e| {
@@ -35,4 +48,85 @@ pub async fn get_session_history_handler(
         })?;
     
     Ok(Json(success(history)))
+}
+
+/// Streams a session's agent run output as Server-Sent Events, the same way
+/// [`crate::api::handlers::agents::stream_agent_run_handler`] does for a run
+/// id directly - this just resolves `session_id` to the backing run first so
+/// a client that only has the session id (the common case for anything
+/// reached via `/api/sessions/*`) doesn't need to know the run's numeric id.
+/// See that handler's doc comment for why a run with no live producer ends
+/// in a synthetic `done` event instead of hanging.
+#[utoipa::path(
+    get,
+    path = "/api/sessions/{session_id}/stream",
+    responses(
+        (status = 200, description = "SSE stream of the session's run output", content_type = "text/event-stream"),
+        (status = 404, description = "Session not found", body = ApiErrorBody),
+    ),
+    params(
+        ("session_id" = String, Path, description = "Session ID"),
+        ("Last-Event-ID" = Option<u64>, Header, description = "Resume after this event sequence number")
+    ),
+    tag = "sessions"
+)]
+pub async fn stream_session_handler(
+    State(app_state): State<AppState>,
+    Extension(ctx): Extension<ApiKeyContext>,
+    Path(session_id): Path<String>,
+    headers: HeaderMap,
+) -> ApiResult<Sse<Pin<Box<dyn Stream<Item = Result<Event, Infallible>> + Send>>>> {
+    ctx.require(ApiKeyScope::AgentsRead)?;
+
+    let (run_id, status) = {
+        let session_id = session_id.clone();
+        crate::api::db::with_conn(&app_state.db_pool, move |conn| {
+            conn.query_row(
+                "SELECT id, status FROM agent_runs WHERE session_id = ?1 ORDER BY created_at DESC LIMIT 1",
+                rusqlite::params![session_id],
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()
+        })
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Session '{}' not found", session_id)))?
+    };
+
+    let run_key = run_id.to_string();
+    let (replay, rx) = app_state
+        .output_broadcaster
+        .subscribe(&run_key, super::agents::last_event_id(&headers));
+
+    let already_done = replay.iter().any(|e| matches!(e, RunEvent::Done { .. }));
+    let is_terminal = matches!(status.as_str(), "completed" | "failed" | "cancelled");
+
+    let replay_stream = stream::iter(
+        replay
+            .into_iter()
+            .map(|e| Ok(super::agents::run_event_to_sse(e))),
+    );
+    let live_stream = BroadcastStream::new(rx).filter_map(|item| async move {
+        match item {
+            Ok(event) => Some(Ok(super::agents::run_event_to_sse(event))),
+            Err(tokio_stream::wrappers::errors::BroadcastStreamRecvError::Lagged(_)) => None,
+        }
+    });
+
+    let combined: Pin<Box<dyn Stream<Item = Result<Event, Infallible>> + Send>> =
+        if is_terminal && !already_done {
+            let synthetic = stream::once(async move {
+                Ok(Event::default()
+                    .event("done")
+                    .data(serde_json::json!({ "exit_code": null }).to_string()))
+            });
+            Box::pin(replay_stream.chain(synthetic))
+        } else {
+            Box::pin(replay_stream.chain(live_stream))
+        };
+
+    Ok(Sse::new(combined).keep_alive(
+        KeepAlive::new()
+            .interval(Duration::from_secs(15))
+            .text("heartbeat"),
+    ))
 }
\ No newline at end of file
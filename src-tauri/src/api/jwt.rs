@@ -0,0 +1,87 @@
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::api::ApiError;
+
+/// How long a login session's token stays valid. Short enough that a
+/// stolen token doesn't grant indefinite access, long enough that a
+/// logged-in browser tab doesn't get kicked out mid-session.
+const TOKEN_LIFETIME_HOURS: i64 = 24;
+
+/// The claims signed into a login token. `sub` carries the user id rather
+/// than the username so a later username change doesn't invalidate
+/// outstanding tokens.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Claims {
+    pub sub: i64,
+    pub username: String,
+    pub exp: i64,
+}
+
+/// The authenticated user, attached to the request's extensions by
+/// [`super::auth::auth_middleware`] once a token decodes and its `exp`
+/// claim is still in the future. Distinct from [`super::auth::ApiKeyContext`]
+/// because a logged-in human and a machine API key carry different
+/// identities and shouldn't be confused by a handler checking `Extension<T>`.
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    pub user_id: i64,
+    pub username: String,
+}
+
+/// Mints a signed login token for `user_id`/`username`, expiring
+/// [`TOKEN_LIFETIME_HOURS`] from now.
+pub fn encode_jwt(user_id: i64, username: &str, secret: &str) -> Result<String, ApiError> {
+    let exp = chrono::Utc::now() + chrono::Duration::hours(TOKEN_LIFETIME_HOURS);
+    let claims = Claims {
+        sub: user_id,
+        username: username.to_string(),
+        exp: exp.timestamp(),
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .map_err(|e| ApiError::Internal(format!("failed to sign login token: {e}")))
+}
+
+/// Validates `token`'s signature and `exp` claim against `secret`.
+/// `jsonwebtoken::Validation` rejects an expired `exp` on its own, so a
+/// successful decode here already means the token is both authentic and
+/// unexpired.
+pub fn decode_jwt(token: &str, secret: &str) -> Result<AuthUser, ApiError> {
+    let data = decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(secret.as_bytes()),
+        &Validation::default(),
+    )
+    .map_err(|e| ApiError::Unauthorized(format!("invalid login token: {e}")))?;
+
+    Ok(AuthUser {
+        user_id: data.claims.sub,
+        username: data.claims.username,
+    })
+}
+
+/// Lets a handler require a logged-in user via the `AuthUser` extractor
+/// instead of pulling `Extension<AuthUser>` and matching on it manually.
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for AuthUser
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<AuthUser>()
+            .cloned()
+            .ok_or((StatusCode::UNAUTHORIZED, "not logged in"))
+    }
+}
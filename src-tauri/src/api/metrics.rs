@@ -0,0 +1,75 @@
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+/// Holds the process-wide Prometheus recorder/exporter installed once by
+/// [`init_metrics`], so the `/metrics` handler can render its current
+/// state without threading a handle through [`super::AppState`].
+static PROMETHEUS_HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Installs the global `metrics` recorder backed by
+/// `metrics-exporter-prometheus`. Must run once, before the server starts
+/// accepting requests - `metrics::counter!`/`histogram!`/`gauge!` calls
+/// made before this silently go nowhere instead of erroring.
+pub fn init_metrics() {
+    let handle = PrometheusBuilder::new()
+        .install_recorder()
+        .expect("failed to install the Prometheus metrics recorder");
+    let _ = PROMETHEUS_HANDLE.set(handle);
+}
+
+/// Renders the current metrics snapshot in the Prometheus text exposition
+/// format, for the `/metrics` handler to return as-is.
+pub fn render() -> String {
+    PROMETHEUS_HANDLE.get().map(|handle| handle.render()).unwrap_or_default()
+}
+
+/// Records a request count, status-code breakdown, and latency for every
+/// request, keyed by the route's *template* (e.g. `/agents/:id`) rather
+/// than the literal path, so `/agents/1` and `/agents/2` aggregate into
+/// one series instead of one per id.
+///
+/// This has to be a [`Router::route_layer`](axum::Router::route_layer)
+/// rather than living in [`super::middleware::middleware_stack`]'s
+/// `Router::layer` stack: [`MatchedPath`] is only present in a request's
+/// extensions once axum has matched it to a route, and a whole-router
+/// `.layer()` runs *before* that matching happens.
+pub async fn metrics_middleware(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|matched| matched.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+
+    let started_at = Instant::now();
+    let response = next.run(request).await;
+    let elapsed = started_at.elapsed();
+
+    let status = response.status().as_u16().to_string();
+    let labels = [
+        ("method", method),
+        ("path", path),
+        ("status", status),
+    ];
+
+    metrics::counter!("http_requests_total", &labels).increment(1);
+    metrics::histogram!("http_request_duration_seconds", &labels).record(elapsed.as_secs_f64());
+
+    response
+}
+
+/// Exports each subsystem `health_check_handler` checks as a
+/// `service_up{service="..."} 1|0` gauge, so a dashboard can alert on one
+/// flipping to `0` instead of only surfacing it in the `/api/health` JSON.
+pub fn record_service_gauges(database_up: bool, checkpoint_up: bool, process_registry_up: bool) {
+    metrics::gauge!("service_up", "service" => "database")
+        .set(if database_up { 1.0 } else { 0.0 });
+    metrics::gauge!("service_up", "service" => "checkpoint_manager")
+        .set(if checkpoint_up { 1.0 } else { 0.0 });
+    metrics::gauge!("service_up", "service" => "process_registry")
+        .set(if process_registry_up { 1.0 } else { 0.0 });
+}
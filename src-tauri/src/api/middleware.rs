@@ -3,10 +3,13 @@ use axum::http::header::{AUTHORIZATION, CONTENT_TYPE};
 use axum::http::{HeaderValue, Method};
 use axum::middleware::Next;
 use axum::response::Response;
+use tower_http::compression::CompressionLayer;
+use tower_http::CompressionLevel;
 use tower_http::cors::CorsLayer;
-use tower_http::trace::TraceLayer;
+use tower_http::trace::{MakeSpan, TraceLayer};
 use tower::ServiceBuilder;
 use std::time::Duration;
+use tracing::Span;
 
 pub fn cors_layer() -> CorsLayer {
     CorsLayer::new()
@@ -27,41 +30,88 @@ pub fn cors_layer() -> CorsLayer {
         .max_age(Duration::from_secs(3600))
 }
 
-pub fn trace_layer() -> TraceLayer<tower_http::classify::SharedClassifier<tower_http::classify::ServerErrorsFailureClass>> {
+/// Builds the span [`TraceLayer`] opens for each request, carrying the id
+/// [`request_id_middleware`] assigned so every line logged while handling
+/// the request shares a `request_id` field, the same correlation the
+/// standalone API server crate's `RequestIdMakeSpan` provides.
+#[derive(Debug, Clone, Default)]
+pub struct RequestIdMakeSpan;
+
+impl<B> MakeSpan<B> for RequestIdMakeSpan {
+    fn make_span(&mut self, request: &axum::http::Request<B>) -> Span {
+        let request_id = request
+            .extensions()
+            .get::<RequestId>()
+            .map(|id| id.0.as_str())
+            .unwrap_or_default();
+
+        tracing::info_span!(
+            "http_request",
+            method = %request.method(),
+            uri = %request.uri(),
+            request_id,
+        )
+    }
+}
+
+pub fn trace_layer() -> TraceLayer<tower_http::classify::SharedClassifier<tower_http::classify::ServerErrorsFailureClass>, RequestIdMakeSpan> {
     TraceLayer::new_for_http()
-        .make_span_with(tower_http::trace::DefaultMakeSpan::new()
-            .level(tracing::Level::INFO))
+        .make_span_with(RequestIdMakeSpan)
         .on_response(tower_http::trace::DefaultOnResponse::new()
             .level(tracing::Level::INFO))
 }
 
-pub fn middleware_stack() -> ServiceBuilder<
+/// Negotiates gzip/brotli/deflate compression (via `Accept-Encoding`) for
+/// every response, so large JSON bodies like agent run lists or full session
+/// history don't go over the wire uncompressed. `quality` trades CPU for
+/// compression ratio; callers pick it from their own configuration.
+pub fn compression_layer(quality: CompressionLevel) -> CompressionLayer {
+    CompressionLayer::new().quality(quality)
+}
+
+pub fn middleware_stack(compression_quality: CompressionLevel) -> ServiceBuilder<
     tower::layer::util::Stack<
-        tower_http::trace::TraceLayer<tower_http::classify::SharedClassifier<tower_http::classify::ServerErrorsFailureClass>>,
-        tower_http::cors::CorsLayer,
+        CompressionLayer,
+        tower::layer::util::Stack<
+            tower_http::trace::TraceLayer<tower_http::classify::SharedClassifier<tower_http::classify::ServerErrorsFailureClass>, RequestIdMakeSpan>,
+            tower_http::cors::CorsLayer,
+        >,
     >
 > {
     ServiceBuilder::new()
         .layer(cors_layer())
         .layer(trace_layer())
+        .layer(compression_layer(compression_quality))
 }
 
-// Optional: Request ID middleware for tracing
-pub async fn request_id_middleware(request: Request, next: Next) -> Response {
-    let request_id = uuid::Uuid::new_v4().to_string();
-    
-    // Add request ID to request extensions for access in handlers
-    let mut request = request;
-    request.extensions_mut().insert(request_id.clone());
-    
+/// The request id [`request_id_middleware`] attaches to a request's
+/// extensions, so [`RequestIdMakeSpan`] and any handler that needs it can
+/// read back the same id that was put on the response header.
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// Stamp every request with an `x-request-id` and a [`RequestId`]
+/// extension, honoring one the caller already supplied (so a request can be
+/// traced from an upstream proxy through this server) and otherwise minting
+/// a UUID, then echo it back on the response so the caller and the server
+/// logs can be correlated by the same id. Must run before [`trace_layer`] so
+/// [`RequestIdMakeSpan`] sees the extension.
+pub async fn request_id_middleware(mut request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get("x-request-id")
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    request.extensions_mut().insert(RequestId(request_id.clone()));
+
     let mut response = next.run(request).await;
-    
-    // Add request ID to response headers
     response.headers_mut().insert(
         "x-request-id",
         HeaderValue::from_str(&request_id).unwrap_or_default(),
     );
-    
+
     response
 }
 
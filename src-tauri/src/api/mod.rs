@@ -2,13 +2,24 @@ pub mod server;
 pub mod routes;
 pub mod handlers;
 pub mod middleware;
+pub mod auth;
+pub mod db;
+pub mod diagnostics;
+pub mod jwt;
+pub mod users;
+pub mod metrics;
+pub mod frontend;
+pub mod streaming;
+pub mod errors;
+pub mod relay;
+pub mod services;
 pub mod error;
 pub mod response;
 pub mod state;
 pub mod compat;
 
-pub use server::ApiServer;
-pub use error::{ApiError, ApiResult};
+pub use server::{ApiServer, TlsConfig};
+pub use error::{ApiError, ApiErrorBody, ApiResult};
 pub use response::ApiResponse;
 pub use state::AppState;
 pub use compat::StateWrapper;
\ No newline at end of file
@@ -0,0 +1,579 @@
+//! Relay mode for fleets of opcode instances that aren't directly routable
+//! (e.g. behind NAT): a relay-mode instance (`opcode api --relay`) accepts
+//! registrations from worker instances over a persistent WebSocket and
+//! forwards `/api/relay/{worker_id}/...` HTTP requests to the matching
+//! worker, streaming its response back to the original caller. A plain
+//! instance can instead dial out to someone else's relay and register as a
+//! worker (`opcode api --relay-connect <url> --relay-worker-id <id>`),
+//! servicing forwarded requests by replaying them against its own router
+//! in-process rather than also binding a publicly reachable port.
+//!
+//! This crate has no equivalent of the standalone API server crate's
+//! `websocket::WebSocketManager` to reuse for the worker connection
+//! registry (`commands`/`process` aren't part of this checkout - see the
+//! note in `api::errors`), so [`RelayManager`] owns its own, built the same
+//! way [`crate::api::streaming::OutputBroadcaster`] owns its per-run
+//! channel registry: a plain `Mutex`-guarded map, not a second dependency.
+//!
+//! The wire format is framed JSON over the WebSocket transport: one
+//! [`RelayRequestFrame`] per forwarded HTTP request, answered by one or
+//! more [`RelayResponseFrame`]s so a streamed response (e.g.
+//! `stream_agent_run_handler`'s SSE body) arrives to the caller
+//! incrementally instead of being buffered in full on the worker.
+//!
+//! A forwarded request's own `Authorization` header only ever authenticated
+//! its caller to *this* relay instance, so it's stripped before framing
+//! rather than replayed against the worker's separate auth store. In its
+//! place, [`RelayManager`] replays the worker's own registration credential
+//! (its [`WorkerConnection::trust_token`]) - an explicit relay-to-worker
+//! hop distinct from the original caller's credential.
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
+    http::{header::AUTHORIZATION, HeaderMap, HeaderName, HeaderValue, Request, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{any, get},
+    Router,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use futures::{sink::SinkExt, stream::StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tower::ServiceExt;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::api::AppState;
+
+/// How long the relay waits for a worker to send the first response frame
+/// for a forwarded request before giving up with a 504.
+const RESPONSE_START_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Capacity of a worker's outbound frame queue and of a single forwarded
+/// request's inbound response-chunk stream.
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Largest body the relay will buffer in memory before forwarding it to a
+/// worker. Relayed requests are API calls, not file uploads.
+const MAX_FORWARD_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+/// Reconnect backoff for [`run_worker`]: starts at one second and doubles up
+/// to this cap, so a relay that's briefly unreachable doesn't get hammered.
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(30);
+
+type WorkerId = String;
+
+/// One HTTP request forwarded to a worker, framed as JSON over its
+/// WebSocket. The body is base64-encoded since WebSocket text frames must be
+/// valid UTF-8.
+#[derive(Debug, Serialize, Deserialize)]
+struct RelayRequestFrame {
+    request_id: String,
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    #[serde(with = "base64_body")]
+    body: Vec<u8>,
+}
+
+/// One chunk of a worker's response to a forwarded request. `status` and
+/// `headers` are only present on the first chunk; `done` marks the final
+/// chunk (possibly empty) so the relay knows to close the caller's response
+/// body instead of waiting on a request the worker has already finished.
+#[derive(Debug, Serialize, Deserialize)]
+struct RelayResponseFrame {
+    request_id: String,
+    #[serde(default)]
+    status: Option<u16>,
+    #[serde(default)]
+    headers: Option<Vec<(String, String)>>,
+    #[serde(default, with = "base64_body")]
+    body: Vec<u8>,
+    done: bool,
+}
+
+mod base64_body {
+    use super::BASE64;
+    use base64::Engine as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &Vec<u8>, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&BASE64.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+        let raw = String::deserialize(d)?;
+        BASE64.decode(raw.as_bytes()).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Why a forwarded request couldn't be delivered to a worker.
+enum RelayError {
+    WorkerNotConnected,
+}
+
+/// A registered worker's outbound frame queue, plus the credential it's
+/// trusted on.
+struct WorkerConnection {
+    sender: mpsc::Sender<Message>,
+    /// The bearer credential the worker itself presented when it dialed in
+    /// to [`register_worker_handler`] (i.e. its own `--relay-api-key`).
+    /// [`RelayManager::forward`] attaches this, not the inbound caller's
+    /// credential, to every request it forwards to the worker - an
+    /// operator provisions the same key in both this relay's and the
+    /// worker's own `api_keys` table for this purpose, so the worker's own
+    /// `auth_middleware` has a credential of its own to check rather than
+    /// being handed one scoped to an entirely different auth store.
+    trust_token: Option<String>,
+}
+
+/// Registry of workers currently dialed into this relay, and the forwarded
+/// requests awaiting a response from them.
+pub struct RelayManager {
+    workers: Mutex<HashMap<WorkerId, WorkerConnection>>,
+    pending: Mutex<HashMap<String, mpsc::Sender<RelayResponseFrame>>>,
+}
+
+impl RelayManager {
+    pub fn new() -> Self {
+        Self {
+            workers: Mutex::new(HashMap::new()),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn insert_worker(&self, worker_id: WorkerId, sender: mpsc::Sender<Message>, trust_token: Option<String>) {
+        self.workers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(worker_id, WorkerConnection { sender, trust_token });
+    }
+
+    fn remove_worker(&self, worker_id: &str) {
+        self.workers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(worker_id);
+    }
+
+    fn worker_connection(&self, worker_id: &str) -> Option<(mpsc::Sender<Message>, Option<String>)> {
+        self.workers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(worker_id)
+            .map(|conn| (conn.sender.clone(), conn.trust_token.clone()))
+    }
+
+    fn insert_pending(&self, request_id: String, sender: mpsc::Sender<RelayResponseFrame>) {
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(request_id, sender);
+    }
+
+    fn remove_pending(&self, request_id: &str) {
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(request_id);
+    }
+
+    fn pending_sender(&self, request_id: &str) -> Option<mpsc::Sender<RelayResponseFrame>> {
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(request_id)
+            .cloned()
+    }
+
+    /// Forward `frame` to `worker_id`, returning a receiver of the response
+    /// chunks the worker sends back. The sender half is also registered as
+    /// pending under `frame.request_id`, which [`handle_worker_connection`]
+    /// looks up as responses arrive.
+    ///
+    /// `frame.headers` arrives with the inbound caller's own `Authorization`
+    /// header already stripped by `forward_handler` - that credential only
+    /// ever meant "let this caller reach this relay" and has nothing to do
+    /// with the worker's own auth store. In its place, this attaches the
+    /// worker's own [`WorkerConnection::trust_token`] so its `auth_middleware`
+    /// has a credential worth checking when it replays the request.
+    async fn forward(
+        &self,
+        worker_id: &str,
+        mut frame: RelayRequestFrame,
+    ) -> Result<mpsc::Receiver<RelayResponseFrame>, RelayError> {
+        let (sender, trust_token) = self.worker_connection(worker_id).ok_or(RelayError::WorkerNotConnected)?;
+        if let Some(token) = trust_token {
+            frame.headers.push(("authorization".to_string(), format!("Bearer {token}")));
+        }
+
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        self.insert_pending(frame.request_id.clone(), tx);
+
+        let payload = match serde_json::to_string(&frame) {
+            Ok(payload) => payload,
+            Err(_) => {
+                self.remove_pending(&frame.request_id);
+                return Err(RelayError::WorkerNotConnected);
+            }
+        };
+
+        if sender.send(Message::Text(payload)).await.is_err() {
+            self.remove_pending(&frame.request_id);
+            return Err(RelayError::WorkerNotConnected);
+        }
+
+        Ok(rx)
+    }
+}
+
+impl Default for RelayManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Relay-side routes: `/relay/connect/{worker_id}` for a worker to dial in,
+/// and a catch-all under `/relay/{worker_id}/` for callers to be forwarded
+/// to it. Merged into `create_protected_routes` only when `--relay` is set,
+/// so it picks up the same `auth_middleware` gate as everything else under
+/// `/api`.
+pub fn relay_router() -> Router<AppState> {
+    Router::new()
+        .route("/relay/connect/:worker_id", get(register_worker_handler))
+        .route("/relay/:worker_id/*rest", any(forward_handler))
+}
+
+/// Upgrade a worker's connection and hand it off to [`handle_worker_connection`],
+/// carrying along the bearer credential the worker registered with so it can
+/// be replayed as the [`WorkerConnection::trust_token`] on every request this
+/// relay later forwards to it.
+async fn register_worker_handler(
+    ws: WebSocketUpgrade,
+    Path(worker_id): Path<String>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+) -> Response {
+    let trust_token = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_string);
+
+    ws.on_upgrade(move |socket| handle_worker_connection(socket, worker_id, app_state.relay_manager, trust_token))
+}
+
+/// Own a registered worker's connection for its lifetime: forward outbound
+/// request frames queued via [`RelayManager::forward`], and route inbound
+/// response frames to whichever pending request they answer.
+async fn handle_worker_connection(
+    socket: WebSocket,
+    worker_id: WorkerId,
+    manager: Arc<RelayManager>,
+    trust_token: Option<String>,
+) {
+    let (mut ws_tx, mut ws_rx) = socket.split();
+    let (out_tx, mut out_rx) = mpsc::channel::<Message>(CHANNEL_CAPACITY);
+    manager.insert_worker(worker_id.clone(), out_tx, trust_token);
+    info!("Worker '{}' registered with relay", worker_id);
+
+    let send_task = tokio::spawn(async move {
+        while let Some(message) = out_rx.recv().await {
+            if ws_tx.send(message).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    while let Some(Ok(message)) = ws_rx.next().await {
+        let Message::Text(text) = message else { continue };
+        let frame: RelayResponseFrame = match serde_json::from_str(&text) {
+            Ok(frame) => frame,
+            Err(e) => {
+                warn!("Worker '{}' sent an unparseable response frame: {}", worker_id, e);
+                continue;
+            }
+        };
+
+        let done = frame.done;
+        let request_id = frame.request_id.clone();
+        if let Some(sender) = manager.pending_sender(&request_id) {
+            let _ = sender.send(frame).await;
+        }
+        if done {
+            manager.remove_pending(&request_id);
+        }
+    }
+
+    manager.remove_worker(&worker_id);
+    send_task.abort();
+    info!("Worker '{}' disconnected from relay", worker_id);
+}
+
+/// Forward an inbound `/api/relay/{worker_id}/{rest}` request to `worker_id`
+/// and stream its response back as it arrives, rather than buffering the
+/// whole thing - this is what lets `stream_agent_run_handler`'s SSE body
+/// pass through a relay hop.
+async fn forward_handler(
+    State(app_state): State<AppState>,
+    Path((worker_id, rest)): Path<(String, String)>,
+    request: Request<Body>,
+) -> Response {
+    let manager = app_state.relay_manager;
+    let method = request.method().to_string();
+    // The caller's `Authorization` header only ever proved it's allowed to
+    // reach *this* relay instance (checked by `auth_middleware` ahead of
+    // this handler) - it's meaningless to the worker's own, separate auth
+    // store and must not be replayed as if it were. `RelayManager::forward`
+    // substitutes the worker's own trust token in its place.
+    let headers = request
+        .headers()
+        .iter()
+        .filter(|(name, _)| *name != AUTHORIZATION)
+        .filter_map(|(name, value)| value.to_str().ok().map(|value| (name.to_string(), value.to_string())))
+        .collect::<Vec<_>>();
+
+    let body = match axum::body::to_bytes(request.into_body(), MAX_FORWARD_BODY_BYTES).await {
+        Ok(body) => body.to_vec(),
+        Err(_) => return StatusCode::PAYLOAD_TOO_LARGE.into_response(),
+    };
+
+    let request_id = Uuid::new_v4().to_string();
+    let frame = RelayRequestFrame {
+        request_id: request_id.clone(),
+        method,
+        path: format!("/{rest}"),
+        headers,
+        body,
+    };
+
+    let mut rx = match manager.forward(&worker_id, frame).await {
+        Ok(rx) => rx,
+        Err(RelayError::WorkerNotConnected) => {
+            return (
+                StatusCode::BAD_GATEWAY,
+                format!("worker '{worker_id}' is not connected to the relay"),
+            )
+                .into_response()
+        }
+    };
+
+    let first = match tokio::time::timeout(RESPONSE_START_TIMEOUT, rx.recv()).await {
+        Ok(Some(frame)) => frame,
+        Ok(None) => {
+            manager.remove_pending(&request_id);
+            return StatusCode::BAD_GATEWAY.into_response();
+        }
+        Err(_) => {
+            manager.remove_pending(&request_id);
+            return gateway_timeout(&request_id);
+        }
+    };
+
+    let status = StatusCode::from_u16(first.status.unwrap_or(200)).unwrap_or(StatusCode::OK);
+    let mut response_headers = HeaderMap::new();
+    for (name, value) in first.headers.clone().unwrap_or_default() {
+        if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(&value)) {
+            response_headers.insert(name, value);
+        }
+    }
+
+    let first_body = Bytes::from(first.body);
+    let rest_stream = ReceiverStream::new(rx).map(|frame| Ok::<_, Infallible>(Bytes::from(frame.body)));
+    let stream = futures::stream::once(async move { Ok::<_, Infallible>(first_body) }).chain(rest_stream);
+
+    let mut response = Response::new(Body::from_stream(stream));
+    *response.status_mut() = status;
+    *response.headers_mut() = response_headers;
+    response
+}
+
+fn gateway_timeout(request_id: &str) -> Response {
+    (
+        StatusCode::GATEWAY_TIMEOUT,
+        format!("worker did not respond to relayed request '{request_id}' in time"),
+    )
+        .into_response()
+}
+
+/// Dial `relay_url` and register as `worker_id`, servicing requests the
+/// relay forwards by handing them to `app` in-process - this instance's own
+/// router, the same one `handle_api_mode` would otherwise bind a port for.
+/// `api_key` is attached to the connect request as a bearer token, since
+/// `/api/relay/connect/:worker_id` sits behind `auth_middleware` whenever
+/// the relay is running with auth enabled; without it every registration
+/// attempt is rejected with 401. It does double duty as the relay-to-worker
+/// trust credential too: the relay replays it, not the original caller's
+/// own credential, on every request it forwards to this worker (see
+/// [`WorkerConnection::trust_token`]), so the same key needs to be a valid
+/// API key in this instance's *own* `api_keys` table as well as the
+/// relay's, if this worker also runs with auth enabled. Runs until the
+/// process exits, reconnecting with capped exponential backoff whenever
+/// the connection to the relay drops.
+pub async fn run_worker(relay_url: String, worker_id: String, api_key: Option<String>, app: Router) {
+    let mut backoff = Duration::from_secs(1);
+    loop {
+        match connect_and_serve(&relay_url, &worker_id, api_key.as_deref(), app.clone()).await {
+            Ok(()) => {
+                info!("Relay connection for worker '{}' closed; reconnecting", worker_id);
+                backoff = Duration::from_secs(1);
+            }
+            Err(e) => {
+                warn!(
+                    "Relay connection for worker '{}' failed: {} - retrying in {:?}",
+                    worker_id, e, backoff
+                );
+            }
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_RECONNECT_BACKOFF);
+    }
+}
+
+async fn connect_and_serve(
+    relay_url: &str,
+    worker_id: &str,
+    api_key: Option<&str>,
+    app: Router,
+) -> Result<(), tokio_tungstenite::tungstenite::Error> {
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+    let url = format!("{}/api/relay/connect/{}", relay_url.trim_end_matches('/'), worker_id);
+    let mut request = url.into_client_request()?;
+    match api_key.and_then(|api_key| HeaderValue::from_str(&format!("Bearer {api_key}")).ok()) {
+        Some(value) => {
+            request.headers_mut().insert(axum::http::header::AUTHORIZATION, value);
+        }
+        None => {
+            warn!(
+                "Connecting to relay '{}' as worker '{}' with no usable --relay-api-key; \
+                 registration will be rejected if the relay has auth enabled",
+                relay_url, worker_id
+            );
+        }
+    }
+
+    let (ws_stream, _) = tokio_tungstenite::connect_async(request).await?;
+    let (ws_tx, mut ws_rx) = ws_stream.split();
+    let ws_tx = Arc::new(tokio::sync::Mutex::new(ws_tx));
+
+    info!("Registered with relay at {} as worker '{}'", relay_url, worker_id);
+
+    while let Some(message) = ws_rx.next().await {
+        let message = message?;
+        let tokio_tungstenite::tungstenite::Message::Text(text) = message else {
+            continue;
+        };
+        let Ok(frame) = serde_json::from_str::<RelayRequestFrame>(&text) else {
+            continue;
+        };
+
+        let app = app.clone();
+        let ws_tx = ws_tx.clone();
+        tokio::spawn(async move {
+            service_forwarded_request(frame, app, ws_tx).await;
+        });
+    }
+
+    Ok(())
+}
+
+type WorkerWsSink = futures::stream::SplitSink<
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+    tokio_tungstenite::tungstenite::Message,
+>;
+
+/// Replay one forwarded request against `app` in-process via [`tower::ServiceExt::oneshot`],
+/// streaming the response back to the relay as one or more [`RelayResponseFrame`]s.
+async fn service_forwarded_request(frame: RelayRequestFrame, app: Router, ws_tx: Arc<tokio::sync::Mutex<WorkerWsSink>>) {
+    let request_id = frame.request_id.clone();
+
+    let mut builder = Request::builder().method(frame.method.as_str()).uri(frame.path.as_str());
+    for (name, value) in &frame.headers {
+        builder = builder.header(name, value);
+    }
+    let request = match builder.body(Body::from(frame.body)) {
+        Ok(request) => request,
+        Err(e) => {
+            warn!("Failed to rebuild relayed request '{}': {}", request_id, e);
+            send_error_response(&ws_tx, &request_id, StatusCode::BAD_REQUEST).await;
+            return;
+        }
+    };
+
+    let response = match app.oneshot(request).await {
+        Ok(response) => response,
+        Err(e) => {
+            error!("In-process replay of relayed request '{}' failed: {:?}", request_id, e);
+            send_error_response(&ws_tx, &request_id, StatusCode::INTERNAL_SERVER_ERROR).await;
+            return;
+        }
+    };
+
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| value.to_str().ok().map(|value| (name.to_string(), value.to_string())))
+        .collect::<Vec<_>>();
+
+    let mut body_stream = response.into_body().into_data_stream();
+    let mut first = true;
+    loop {
+        let chunk = body_stream.next().await;
+        let (body, done) = match chunk {
+            Some(Ok(bytes)) => (bytes.to_vec(), false),
+            Some(Err(_)) | None => (Vec::new(), true),
+        };
+
+        let response_frame = RelayResponseFrame {
+            request_id: request_id.clone(),
+            status: if first { Some(status) } else { None },
+            headers: if first { Some(headers.clone()) } else { None },
+            body,
+            done,
+        };
+        first = false;
+
+        let Ok(payload) = serde_json::to_string(&response_frame) else { break };
+        if ws_tx
+            .lock()
+            .await
+            .send(tokio_tungstenite::tungstenite::Message::Text(payload))
+            .await
+            .is_err()
+        {
+            break;
+        }
+
+        if done {
+            break;
+        }
+    }
+}
+
+async fn send_error_response(ws_tx: &Arc<tokio::sync::Mutex<WorkerWsSink>>, request_id: &str, status: StatusCode) {
+    let frame = RelayResponseFrame {
+        request_id: request_id.to_string(),
+        status: Some(status.as_u16()),
+        headers: Some(Vec::new()),
+        body: Vec::new(),
+        done: true,
+    };
+    if let Ok(payload) = serde_json::to_string(&frame) {
+        let _ = ws_tx
+            .lock()
+            .await
+            .send(tokio_tungstenite::tungstenite::Message::Text(payload))
+            .await;
+    }
+}
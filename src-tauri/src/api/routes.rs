@@ -1,39 +1,82 @@
+use crate::api::auth::auth_middleware;
 use crate::api::handlers::{
     // Agents
     list_agents_handler, get_agent_handler, create_agent_handler,
     execute_agent_handler, list_agent_runs_handler, list_all_agent_runs_handler,
+    get_run_errors_handler, stream_agent_run_handler,
     // Projects
     list_projects_handler, get_project_sessions_handler,
     // Sessions
-    get_session_history_handler,
+    get_session_history_handler, stream_session_handler,
     // Health
     health_check_handler,
+    // Auth
+    mint_api_key_handler, revoke_api_key_handler, login_handler,
+    // Diagnostics
+    get_diagnostics_handler,
 };
 use crate::api::AppState;
-use axum::routing::{get, post};
+use axum::routing::{delete, get, post};
 use axum::Router;
 
-pub fn create_routes() -> Router<AppState> {
-    Router::new()
-        // Health check
-        .route("/health", get(health_check_handler))
-        
+/// Everything under `/api` except `/health` and `/auth/login` requires
+/// credentials (see `auth::auth_middleware`), so it's split into its own
+/// router with the auth layer applied, and merged alongside those public
+/// routes below.
+///
+/// `relay_enabled` mounts `/api/relay/*` alongside everything else - a
+/// worker registering or a caller being forwarded needs the same API key
+/// auth as any other protected route, so this is where it's wired in
+/// rather than as a separately-authed router.
+fn create_protected_routes(relay_enabled: bool) -> Router<AppState> {
+    let mut router = Router::new()
         // Agent routes
         .route("/agents", get(list_agents_handler).post(create_agent_handler))
         .route("/agents/:id", get(get_agent_handler))
         .route("/agents/:id/execute", post(execute_agent_handler))
         .route("/agents/:id/runs", get(list_agent_runs_handler))
         .route("/agents/runs", get(list_all_agent_runs_handler))
-        
+        .route("/agents/runs/:id/errors", get(get_run_errors_handler))
+        .route("/agents/runs/:id/stream", get(stream_agent_run_handler))
+
         // Project routes
         .route("/projects", get(list_projects_handler))
         .route("/projects/:project_id/sessions", get(get_project_sessions_handler))
-        
+
         // Session routes
         .route("/sessions/:session_id/history/:project_id", get(get_session_history_handler))
+        .route("/sessions/:session_id/stream", get(stream_session_handler))
+
+        // API key management
+        .route("/auth/keys", post(mint_api_key_handler))
+        .route("/auth/keys/:id", delete(revoke_api_key_handler))
+
+        // Diagnostics
+        .route("/diagnostics", get(get_diagnostics_handler));
+
+    if relay_enabled {
+        router = router.merge(crate::api::relay::relay_router());
+    }
+
+    router.route_layer(axum::middleware::from_fn(auth_middleware))
+}
+
+pub fn create_routes(relay_enabled: bool) -> Router<AppState> {
+    Router::new()
+        // Health check - left unauthenticated so it can be used as a
+        // liveness probe without provisioning an API key
+        .route("/health", get(health_check_handler))
+        // Login issues the token auth_middleware checks everywhere else,
+        // so it can't require one itself
+        .route("/auth/login", post(login_handler))
+        .merge(create_protected_routes(relay_enabled))
+        // `route_layer`, not `layer`: `metrics_middleware` reads the
+        // matched route template from `MatchedPath`, which axum only
+        // attaches once a request has matched a route in *this* router.
+        .route_layer(axum::middleware::from_fn(crate::api::metrics::metrics_middleware))
 }
 
-pub fn create_api_routes() -> Router<AppState> {
+pub fn create_api_routes(relay_enabled: bool) -> Router<AppState> {
     Router::new()
-        .nest("/api", create_routes())
+        .nest("/api", create_routes(relay_enabled))
 }
\ No newline at end of file
@@ -1,19 +1,34 @@
 use crate::api::{
-    routes::create_api_routes, middleware::middleware_stack, AppState, ApiError
+    db::DbPool, routes::create_api_routes, middleware::middleware_stack, AppState,
 };
-use crate::commands::agents::AgentDb;
 use crate::checkpoint::state::CheckpointState;
 use crate::process::ProcessRegistryState;
 use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::net::TcpListener;
 use tower::ServiceBuilder;
+use tower_http::CompressionLevel;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 use utoipa_rapidoc::RapiDoc;
 use utoipa_redoc::Redoc;
 
+/// Cert/key pair for terminating TLS directly in the API server, for
+/// deployments that expose it beyond localhost without a reverse proxy in
+/// front. There's no `AppConfig`/config-file type in this crate (unlike the
+/// standalone API server crate), so this is populated from CLI flags the
+/// same way `--compression-level` is in `handle_api_mode`. Reloading the
+/// cert/key pair on SIGHUP is not implemented here — restart the process to
+/// pick up a renewed certificate.
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
 #[derive(OpenApi)]
 #[openapi(
     paths(
@@ -24,9 +39,17 @@ use utoipa_redoc::Redoc;
         crate::api::handlers::execute_agent_handler,
         crate::api::handlers::list_agent_runs_handler,
         crate::api::handlers::list_all_agent_runs_handler,
+        crate::api::handlers::get_run_errors_handler,
+        crate::api::handlers::stream_agent_run_handler,
+        crate::api::handlers::get_diagnostics_handler,
         crate::api::handlers::list_projects_handler,
         crate::api::handlers::get_project_sessions_handler,
         crate::api::handlers::get_session_history_handler,
+        crate::api::handlers::stream_session_handler,
+        crate::api::handlers::mint_api_key_handler,
+        crate::api::handlers::revoke_api_key_handler,
+        crate::api::handlers::login_handler,
+        crate::api::handlers::metrics_handler,
     ),
     components(
         schemas(
@@ -34,6 +57,14 @@ use utoipa_redoc::Redoc;
             crate::api::handlers::ExecuteAgentRequest,
             crate::api::handlers::HealthResponse,
             crate::api::handlers::ServiceStatus,
+            crate::api::db::PoolStatus,
+            crate::api::diagnostics::Reportable,
+            crate::api::diagnostics::DiagnosticsSnapshot,
+            crate::api::handlers::MintApiKeyRequest,
+            crate::api::handlers::MintApiKeyResponse,
+            crate::api::handlers::LoginRequest,
+            crate::api::handlers::LoginResponse,
+            crate::api::errors::RunError,
             crate::commands::agents::Agent,
             crate::commands::agents::AgentRun,
             crate::commands::agents::AgentRunWithMetrics,
@@ -43,6 +74,7 @@ use utoipa_redoc::Redoc;
             crate::api::response::ApiResponse<Vec<crate::commands::agents::Agent>>,
             crate::api::response::ApiResponse<crate::commands::agents::Agent>,
             crate::api::response::ApiResponse<crate::api::handlers::HealthResponse>,
+            crate::api::ApiErrorBody,
         )
     ),
     tags(
@@ -50,6 +82,8 @@ use utoipa_redoc::Redoc;
         (name = "agents", description = "Agent management endpoints"),
         (name = "projects", description = "Project management endpoints"),
         (name = "sessions", description = "Session management endpoints"),
+        (name = "auth", description = "API key management and login endpoints"),
+        (name = "diagnostics", description = "Runtime error reporting and diagnostics endpoints"),
     ),
     info(
         title = "Opcode HTTP API",
@@ -73,58 +107,147 @@ pub struct ApiDoc;
 pub struct ApiServer {
     app_state: AppState,
     port: u16,
+    /// Interface to listen on, `--bind` in `handle_api_mode`. Defaults to
+    /// loopback so today's behavior is unchanged when unset; TLS alone
+    /// doesn't make the server reachable off the local machine without
+    /// also binding a non-loopback address here.
+    bind_host: std::net::IpAddr,
+    compression_level: CompressionLevel,
+    tls_config: Option<TlsConfig>,
+    /// Whether this instance exposes `/api/relay/*`, accepting worker
+    /// registrations and forwarding requests to them. Set by `opcode api
+    /// --relay`.
+    relay_enabled: bool,
 }
 
 impl ApiServer {
     pub fn new(
-        agent_db: AgentDb,
+        db_pool: DbPool,
+        checkpoint_state: CheckpointState,
+        process_registry: ProcessRegistryState,
+        port: Option<u16>,
+        compression_level: Option<CompressionLevel>,
+        jwt_secret: String,
+        auth_enabled: bool,
+        diagnostics_sink_url: Option<String>,
+    ) -> Self {
+        Self::with_tls(
+            db_pool,
+            checkpoint_state,
+            process_registry,
+            port,
+            None,
+            compression_level,
+            None,
+            jwt_secret,
+            auth_enabled,
+            diagnostics_sink_url,
+            false,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_tls(
+        db_pool: DbPool,
         checkpoint_state: CheckpointState,
         process_registry: ProcessRegistryState,
         port: Option<u16>,
+        bind_host: Option<std::net::IpAddr>,
+        compression_level: Option<CompressionLevel>,
+        tls_config: Option<TlsConfig>,
+        jwt_secret: String,
+        auth_enabled: bool,
+        diagnostics_sink_url: Option<String>,
+        relay_enabled: bool,
     ) -> Self {
-        let app_state = AppState::new(agent_db, checkpoint_state, process_registry);
+        let app_state = AppState::new(
+            db_pool,
+            checkpoint_state,
+            process_registry,
+            jwt_secret,
+            auth_enabled,
+            diagnostics_sink_url,
+        );
         let port = port.unwrap_or(3001);
-        
-        Self { app_state, port }
+        let bind_host = bind_host.unwrap_or_else(|| std::net::IpAddr::from([127, 0, 0, 1]));
+        let compression_level = compression_level.unwrap_or(CompressionLevel::Default);
+
+        Self { app_state, port, bind_host, compression_level, tls_config, relay_enabled }
     }
-    
+
     pub async fn start(self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let addr = SocketAddr::from((self.bind_host, self.port));
+        let tls_config = self.tls_config.clone();
         let app = self.create_app();
-        let addr = SocketAddr::from(([127, 0, 0, 1], self.port));
-        
-        log::info!("🚀 Starting HTTP API server on http://{}", addr);
+
         log::info!("📚 API Documentation available at:");
-        log::info!("   - Swagger UI: http://{}/docs", addr);
+        log::info!("   - Swagger UI: http://{}/docs (also served at /api/docs)", addr);
         log::info!("   - RapiDoc: http://{}/rapidoc", addr);
         log::info!("   - ReDoc: http://{}/redoc", addr);
-        log::info!("   - OpenAPI JSON: http://{}/api-docs/openapi.json", addr);
-        
-        let listener = TcpListener::bind(addr).await?;
-        axum::serve(listener, app).await?;
-        
+        log::info!("   - OpenAPI JSON: http://{}/api-docs/openapi.json (also served at /api/openapi.json)", addr);
+
+        match tls_config {
+            Some(tls) => {
+                log::info!("🔒 Starting HTTPS API server on https://{}", addr);
+                let rustls_config = RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path).await?;
+                axum_server::bind_rustls(addr, rustls_config)
+                    .serve(app.into_make_service())
+                    .await?;
+            }
+            None => {
+                log::info!("🚀 Starting HTTP API server on http://{}", addr);
+                let listener = TcpListener::bind(addr).await?;
+                axum::serve(listener, app).await?;
+            }
+        }
+
         Ok(())
     }
     
-    fn create_app(self) -> Router {
-        let api_routes = create_api_routes();
-        
+    fn create_app(&self) -> Router {
+        let api_routes = create_api_routes(self.relay_enabled);
+
         Router::new()
             // API routes
             .merge(api_routes)
-            
+
+            // Prometheus scrape endpoint - left unauthenticated and outside
+            // `/api`, the same way `/health` is, so a scraper doesn't need
+            // an API key.
+            .route("/metrics", axum::routing::get(crate::api::handlers::metrics_handler))
+
             // Documentation routes
             .merge(SwaggerUi::new("/docs").url("/api-docs/openapi.json", ApiDoc::openapi()))
             .merge(RapiDoc::new("/api-docs/openapi.json").path("/rapidoc"))
             .merge(Redoc::with_url("/redoc", ApiDoc::openapi()))
-            
+            // Also serve the spec and docs under /api, alongside the routes
+            // it describes, for clients that expect everything namespaced
+            // under the API prefix.
+            .merge(SwaggerUi::new("/api/docs").url("/api/openapi.json", ApiDoc::openapi()))
+
+            // Embedded frontend - only reached for a path none of the
+            // routes merged above matched, so `/api/*`, `/docs`, `/redoc`,
+            // `/rapidoc`, and `/metrics` all still take precedence.
+            .fallback(crate::api::frontend::serve_frontend)
+
             // Apply middleware
-            .layer(middleware_stack())
-            
+            .layer(middleware_stack(self.compression_level))
+            // Outermost: assigns/echoes `x-request-id` and puts it on the
+            // request so `trace_layer()`'s spans (inside `middleware_stack`)
+            // can tag every log line with it.
+            .layer(axum::middleware::from_fn(crate::api::middleware::request_id_middleware))
+
             // Set application state
-            .with_state(self.app_state)
+            .with_state(self.app_state.clone())
     }
-    
-    pub fn get_app_for_testing(self) -> Router {
+
+    /// Builds the same `Router` [`Self::start`] would serve, without
+    /// consuming `self` or binding a port. Originally added only for tests;
+    /// also what a `--relay-connect` worker hands to
+    /// [`crate::api::relay::run_worker`] so forwarded requests are replayed
+    /// against this instance's own routes in-process, the same router a
+    /// direct caller would hit.
+    pub fn get_app_for_testing(&self) -> Router {
         self.create_app()
     }
 }
\ No newline at end of file
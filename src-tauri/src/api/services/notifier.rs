@@ -0,0 +1,200 @@
+//! Fires HTTP webhooks when an agent run transitions to a terminal status,
+//! so callers driving agents headlessly through the API don't have to poll
+//! `GET /api/agents/runs/{id}` to find out a long run finished.
+//!
+//! The actual `Running -> Completed/Failed/Cancelled` transition happens
+//! wherever an agent run's status is updated — `execute_agent` and the
+//! process-registry cleanup in `commands/agents.rs` — but that module isn't
+//! part of this checkout (only `main.rs` and `api/` are present here), so
+//! this wires the config, signing, delivery-with-retry, and background
+//! task; calling [`Notifier::notify`] from those transition sites is left
+//! for whoever lands `commands/agents.rs`.
+//!
+//! **Status: partial.** `Notifier::notify` has no caller anywhere in this
+//! checkout as of this module landing - no webhook will ever fire. A
+//! `WebhookPayload` needs a real, completed `agent_run` to report (its
+//! `status`/`duration_ms`/`total_tokens` fields describe one), and nothing
+//! in this checkout ever runs or transitions one (`execute_agent_handler`
+//! is a stub that always returns an error), so there's no genuine transition here to notify on
+//! without fabricating one.
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::api::db::DbPool;
+
+const CHANNEL_CAPACITY: usize = 256;
+const MAX_DELIVERY_ATTEMPTS: u32 = 3;
+const WEBHOOK_CONFIG_KEY: &str = "webhook_config";
+
+/// Stored as JSON under `app_settings.webhook_config`, the same generic
+/// key/value store `handle_api_mode` already creates for other settings.
+#[derive(Debug, Deserialize)]
+struct WebhookConfig {
+    urls: Vec<String>,
+    /// Which of `Completed` / `Failed` / `Cancelled` to notify on. An empty
+    /// list means "all of them".
+    #[serde(default)]
+    notify_statuses: Vec<String>,
+    secret: String,
+}
+
+/// The JSON body POSTed to each configured webhook URL. Deliberately its
+/// own type rather than `AgentRunWithMetrics` directly, so this module
+/// doesn't take a hard dependency on a type defined in `commands/agents.rs`
+/// (absent from this checkout) — the field list matches it regardless.
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookPayload {
+    pub id: i64,
+    pub agent_name: String,
+    pub task: String,
+    pub status: String,
+    pub duration_ms: Option<i64>,
+    pub total_tokens: Option<i64>,
+    pub project_path: String,
+}
+
+/// Handle used to report a run transition without blocking the caller —
+/// delivery (including retries) happens entirely on the background task
+/// spawned by [`Notifier::spawn`].
+#[derive(Clone)]
+pub struct Notifier {
+    tx: mpsc::Sender<WebhookPayload>,
+}
+
+impl Notifier {
+    /// Spawns the long-lived task that owns the receiver and delivers each
+    /// reported transition to the configured webhook URLs. Must be called
+    /// from within a Tokio runtime.
+    pub fn spawn(db_pool: DbPool) -> Self {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        tokio::spawn(run_consumer(db_pool, rx));
+        Self { tx }
+    }
+
+    /// Reports a terminal-status transition. Never blocks: if the channel
+    /// is full the transition is dropped and logged rather than stalling
+    /// the agent executor.
+    pub fn notify(&self, payload: WebhookPayload) {
+        if self.tx.try_send(payload).is_err() {
+            log::warn!("webhook notifier channel full or closed; dropping notification");
+        }
+    }
+}
+
+fn load_config(conn: &rusqlite::Connection) -> Option<WebhookConfig> {
+    let raw: String = conn
+        .query_row(
+            "SELECT value FROM app_settings WHERE key = ?1",
+            rusqlite::params![WEBHOOK_CONFIG_KEY],
+            |row| row.get(0),
+        )
+        .ok()?;
+
+    match serde_json::from_str(&raw) {
+        Ok(config) => Some(config),
+        Err(e) => {
+            log::warn!("Ignoring malformed {} setting: {}", WEBHOOK_CONFIG_KEY, e);
+            None
+        }
+    }
+}
+
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts a key of any length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+async fn run_consumer(db_pool: DbPool, mut rx: mpsc::Receiver<WebhookPayload>) {
+    let client = reqwest::Client::new();
+
+    while let Some(payload) = rx.recv().await {
+        let config = match crate::api::db::with_conn(&db_pool, |conn| Ok(load_config(&conn))).await {
+            Ok(config) => config,
+            Err(e) => {
+                log::error!("Failed to load webhook config from pool: {}", e);
+                None
+            }
+        };
+
+        let Some(config) = config else {
+            continue;
+        };
+
+        if !config.notify_statuses.is_empty() && !config.notify_statuses.contains(&payload.status) {
+            continue;
+        }
+
+        let body = match serde_json::to_vec(&payload) {
+            Ok(body) => body,
+            Err(e) => {
+                log::error!("Failed to serialize webhook payload for run {}: {}", payload.id, e);
+                continue;
+            }
+        };
+        let signature = sign(&config.secret, &body);
+
+        for url in &config.urls {
+            deliver_with_retry(&client, url, &body, &signature, payload.id).await;
+        }
+    }
+}
+
+async fn deliver_with_retry(
+    client: &reqwest::Client,
+    url: &str,
+    body: &[u8],
+    signature: &str,
+    run_id: i64,
+) {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+
+        let result = client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .header("X-Opcode-Signature", signature)
+            .body(body.to_vec())
+            .send()
+            .await;
+
+        let delivered = matches!(&result, Ok(resp) if resp.status().is_success());
+        if delivered {
+            return;
+        }
+
+        let description = match result {
+            Ok(resp) => format!("HTTP {}", resp.status()),
+            Err(e) => e.to_string(),
+        };
+
+        if attempt >= MAX_DELIVERY_ATTEMPTS {
+            log::error!(
+                "Giving up delivering webhook for run {} to {} after {} attempts: {}",
+                run_id,
+                url,
+                MAX_DELIVERY_ATTEMPTS,
+                description
+            );
+            return;
+        }
+
+        let backoff_ms = 200u64 * (1 << (attempt - 1));
+        log::warn!(
+            "Webhook delivery for run {} to {} failed (attempt {}/{}): {} - retrying in {}ms",
+            run_id,
+            url,
+            attempt,
+            MAX_DELIVERY_ATTEMPTS,
+            description,
+            backoff_ms
+        );
+        tokio::time::sleep(Duration::from_millis(backoff_ms)).await;
+    }
+}
@@ -1,4 +1,9 @@
-use crate::commands::agents::AgentDb;
+use crate::api::db::DbPool;
+use crate::api::diagnostics::DiagnosticsReporter;
+use crate::api::errors::ErrorReporter;
+use crate::api::relay::RelayManager;
+use crate::api::services::notifier::Notifier;
+use crate::api::streaming::OutputBroadcaster;
 use crate::checkpoint::state::CheckpointState;
 use crate::process::ProcessRegistryState;
 use axum::extract::FromRef;
@@ -6,29 +11,73 @@ use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct AppState {
-    pub agent_db: Arc<AgentDb>,
+    /// Pooled SQLite access backing every handler and background consumer
+    /// that used to go through `AgentDb`'s single `Mutex<Connection>`. A
+    /// `deadpool_sqlite::Pool` is already cheap to clone (it's an `Arc`
+    /// internally), so this isn't wrapped in one of our own.
+    pub db_pool: DbPool,
     pub checkpoint_state: Arc<CheckpointState>,
     pub process_registry: Arc<ProcessRegistryState>,
+    pub error_reporter: Arc<ErrorReporter>,
+    pub notifier: Arc<Notifier>,
+    /// Generic runtime-failure channel backing `GET /api/diagnostics` and
+    /// the error-rate check folded into `health_check_handler`. Broader in
+    /// scope than `error_reporter`, which only ever covers agent-run
+    /// failures tied to an `agent_run_id`.
+    pub diagnostics: Arc<DiagnosticsReporter>,
+    /// Per-run SSE channels backing `GET /api/agents/runs/{id}/stream` and
+    /// `GET /api/sessions/{id}/stream`.
+    pub output_broadcaster: Arc<OutputBroadcaster>,
+    /// Registry of workers dialed into this instance's `/api/relay/*`
+    /// routes. Always constructed, same as `output_broadcaster`, even if
+    /// `--relay` wasn't passed and the routes are never mounted - cheap and
+    /// one less thing to make optional.
+    pub relay_manager: Arc<RelayManager>,
+    /// HS256 signing key for login tokens minted by
+    /// `handlers::auth::login_handler` and checked by `auth::auth_middleware`.
+    pub jwt_secret: Arc<String>,
+    /// Whether `auth::auth_middleware` enforces credentials at all. `false`
+    /// is the local-dev escape hatch so the server can run without anyone
+    /// having provisioned an API key or a user account first.
+    pub auth_enabled: bool,
 }
 
 impl AppState {
+    /// `db_pool` is built by the caller with the configurable connection
+    /// cap the old single-`Mutex` `AgentDb` had no equivalent of
+    /// (`--db-pool-size` in `main.rs`, falling back to
+    /// [`crate::api::db::DEFAULT_POOL_SIZE`]).
     pub fn new(
-        agent_db: AgentDb,
+        db_pool: DbPool,
         checkpoint_state: CheckpointState,
         process_registry: ProcessRegistryState,
+        jwt_secret: String,
+        auth_enabled: bool,
+        diagnostics_sink_url: Option<String>,
     ) -> Self {
+        let error_reporter = Arc::new(ErrorReporter::spawn(db_pool.clone()));
+        let notifier = Arc::new(Notifier::spawn(db_pool.clone()));
+        let diagnostics = Arc::new(DiagnosticsReporter::spawn(diagnostics_sink_url));
+
         Self {
-            agent_db: Arc::new(agent_db),
+            db_pool,
             checkpoint_state: Arc::new(checkpoint_state),
             process_registry: Arc::new(process_registry),
+            error_reporter,
+            notifier,
+            diagnostics,
+            output_broadcaster: Arc::new(OutputBroadcaster::new()),
+            relay_manager: Arc::new(RelayManager::new()),
+            jwt_secret: Arc::new(jwt_secret),
+            auth_enabled,
         }
     }
 }
 
 // Enable extraction of individual components from AppState
-impl FromRef<AppState> for Arc<AgentDb> {
+impl FromRef<AppState> for DbPool {
     fn from_ref(state: &AppState) -> Self {
-        state.agent_db.clone()
+        state.db_pool.clone()
     }
 }
 
@@ -42,4 +91,34 @@ impl FromRef<AppState> for Arc<ProcessRegistryState> {
     fn from_ref(state: &AppState) -> Self {
         state.process_registry.clone()
     }
+}
+
+impl FromRef<AppState> for Arc<ErrorReporter> {
+    fn from_ref(state: &AppState) -> Self {
+        state.error_reporter.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<Notifier> {
+    fn from_ref(state: &AppState) -> Self {
+        state.notifier.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<DiagnosticsReporter> {
+    fn from_ref(state: &AppState) -> Self {
+        state.diagnostics.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<OutputBroadcaster> {
+    fn from_ref(state: &AppState) -> Self {
+        state.output_broadcaster.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<RelayManager> {
+    fn from_ref(state: &AppState) -> Self {
+        state.relay_manager.clone()
+    }
 }
\ No newline at end of file
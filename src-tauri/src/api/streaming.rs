@@ -0,0 +1,143 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use tokio::sync::broadcast;
+
+/// How many recent lines each run's channel keeps around so a client that
+/// reconnects with `Last-Event-ID` doesn't lose output that arrived while
+/// it was disconnected. Matches the channel's own broadcast capacity -
+/// past that, a slow subscriber would start missing lines anyway.
+const TAIL_BUFFER_LEN: usize = 256;
+
+/// One line of output, or the run's completion, published for an agent
+/// run's SSE stream. `seq` is this channel's own monotonic counter, used
+/// as the SSE event id so a reconnecting client's `Last-Event-ID` can be
+/// matched against the tail buffer.
+#[derive(Debug, Clone)]
+pub enum RunEvent {
+    Line { seq: u64, text: String },
+    Done { seq: u64, exit_code: Option<i32> },
+}
+
+impl RunEvent {
+    pub fn seq(&self) -> u64 {
+        match self {
+            RunEvent::Line { seq, .. } => *seq,
+            RunEvent::Done { seq, .. } => *seq,
+        }
+    }
+}
+
+struct RunChannel {
+    tx: broadcast::Sender<RunEvent>,
+    tail: Mutex<VecDeque<RunEvent>>,
+    next_seq: AtomicU64,
+}
+
+impl RunChannel {
+    fn new() -> Self {
+        let (tx, _) = broadcast::channel(TAIL_BUFFER_LEN);
+        Self {
+            tx,
+            tail: Mutex::new(VecDeque::with_capacity(TAIL_BUFFER_LEN)),
+            next_seq: AtomicU64::new(0),
+        }
+    }
+
+    fn publish(&self, build: impl FnOnce(u64) -> RunEvent) {
+        let seq = self.next_seq.fetch_add(1, Ordering::SeqCst);
+        let event = build(seq);
+
+        {
+            let mut tail = self.tail.lock().unwrap_or_else(|e| e.into_inner());
+            if tail.len() == TAIL_BUFFER_LEN {
+                tail.pop_front();
+            }
+            tail.push_back(event.clone());
+        }
+
+        // No subscribers is the common case (nothing's watching this run
+        // right now) - that's not an error, just a send with no effect.
+        let _ = self.tx.send(event);
+    }
+}
+
+/// Registry of per-run broadcast channels backing
+/// `GET /api/agents/runs/{id}/stream` and `GET /api/sessions/{id}/stream`.
+///
+/// This is the piece of the streaming feature this crate can actually own
+/// today. The other half - a process supervisor reading a spawned Claude
+/// process's stdout/stderr and calling [`OutputBroadcaster::publish_line`]
+/// as lines arrive - belongs in `commands::claude`/`process`, neither of
+/// which exist in this checkout yet (`execute_agent_handler` itself
+/// always returns an error, since HTTP-triggered agent execution isn't
+/// implemented).
+/// Once that lands, it only needs to call `publish_line`/`publish_done`
+/// with the run id; every subscriber set up here will just start
+/// receiving output.
+pub struct OutputBroadcaster {
+    channels: Mutex<HashMap<String, std::sync::Arc<RunChannel>>>,
+}
+
+impl OutputBroadcaster {
+    pub fn new() -> Self {
+        Self {
+            channels: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn channel(&self, run_id: &str) -> std::sync::Arc<RunChannel> {
+        self.channels
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(run_id.to_string())
+            .or_insert_with(|| std::sync::Arc::new(RunChannel::new()))
+            .clone()
+    }
+
+    /// Publishes one JSONL line of output from `run_id`'s process.
+    pub fn publish_line(&self, run_id: &str, text: String) {
+        self.channel(run_id).publish(|seq| RunEvent::Line { seq, text });
+    }
+
+    /// Publishes the run's completion. Subscribers treat this as the end
+    /// of the stream.
+    pub fn publish_done(&self, run_id: &str, exit_code: Option<i32>) {
+        self.channel(run_id).publish(|seq| RunEvent::Done { seq, exit_code });
+    }
+
+    /// Subscribes to `run_id`'s channel, returning the buffered tail
+    /// events after `last_event_id` (all of them if `None`) alongside a
+    /// receiver for everything published from now on. Replaying the tail
+    /// first and then switching to live events can double-deliver an
+    /// event right at the boundary if one is published in between; the
+    /// `seq` on each event lets a client dedupe, which is the same
+    /// trade-off `Last-Event-Id` replay makes over SSE in general.
+    pub fn subscribe(
+        &self,
+        run_id: &str,
+        last_event_id: Option<u64>,
+    ) -> (Vec<RunEvent>, broadcast::Receiver<RunEvent>) {
+        let channel = self.channel(run_id);
+        let rx = channel.tx.subscribe();
+
+        let tail = channel.tail.lock().unwrap_or_else(|e| e.into_inner());
+        let replay = tail
+            .iter()
+            .filter(|event| match last_event_id {
+                Some(last) => event.seq() > last,
+                None => true,
+            })
+            .cloned()
+            .collect();
+
+        (replay, rx)
+    }
+}
+
+impl Default for OutputBroadcaster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,118 @@
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use rand::Rng;
+use rusqlite::OptionalExtension;
+
+use crate::api::ApiError;
+
+/// A login-capable account, distinct from an [`super::auth::ApiKeyContext`]:
+/// users authenticate with a password through `/api/auth/login` and get
+/// back a JWT, while API keys are minted out-of-band for machine clients.
+#[derive(Debug, Clone)]
+pub struct User {
+    pub id: i64,
+    pub username: String,
+    password_hash: String,
+}
+
+/// Creates the `users` table if it doesn't exist yet, the same way
+/// [`super::auth::ensure_api_keys_table`] bootstraps `api_keys`.
+pub fn ensure_users_table(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS users (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            username TEXT NOT NULL UNIQUE,
+            password_hash TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+fn hash_password(raw: &str) -> Result<String, ApiError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(raw.as_bytes(), &salt)
+        .map(|h| h.to_string())
+        .map_err(|e| ApiError::Internal(format!("failed to hash password: {e}")))
+}
+
+fn verify_password(raw: &str, hash: &str) -> bool {
+    let Ok(parsed) = PasswordHash::new(hash) else {
+        return false;
+    };
+    Argon2::default().verify_password(raw.as_bytes(), &parsed).is_ok()
+}
+
+/// Creates a new login account. Returns [`ApiError::Conflict`] if the
+/// username is already taken, mirroring the `UNIQUE` constraint on the
+/// `username` column.
+pub fn create_user(conn: &rusqlite::Connection, username: &str, password: &str) -> Result<i64, ApiError> {
+    let hash = hash_password(password)?;
+
+    conn.execute(
+        "INSERT INTO users (username, password_hash) VALUES (?1, ?2)",
+        rusqlite::params![username, hash],
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::SqliteFailure(ref f, _) if f.code == rusqlite::ErrorCode::ConstraintViolation => {
+            ApiError::Conflict(format!("username '{}' is already taken", username))
+        }
+        e => ApiError::DatabaseError(e.to_string()),
+    })?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+/// If no login accounts exist yet (fresh database), creates an `admin`
+/// account with a random password and prints it once, the same way
+/// [`super::auth::bootstrap_admin_key_if_empty`] seeds `api_keys`. Without
+/// this the `users` table would stay permanently empty and
+/// `/api/auth/login` could never succeed for anyone.
+pub fn bootstrap_admin_user_if_empty(conn: &rusqlite::Connection) -> Result<(), ApiError> {
+    let count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM users", [], |row| row.get(0))
+        .map_err(|e| ApiError::DatabaseError(e.to_string()))?;
+    if count > 0 {
+        return Ok(());
+    }
+
+    let password: String = (0..24)
+        .map(|_| rand::thread_rng().sample(rand::distributions::Alphanumeric) as char)
+        .collect();
+
+    create_user(conn, "admin", &password)?;
+
+    println!("🔑 No login accounts found — created 'admin' with a generated password:");
+    println!("   {}", password);
+    println!("   Save this now; it will not be shown again.");
+
+    Ok(())
+}
+
+/// Looks up `username` and checks `password` against its stored hash.
+/// Returns `Ok(None)` for either an unknown username or a wrong password -
+/// callers should not distinguish the two in the response they send back,
+/// so as not to leak which usernames exist.
+pub fn verify_user_credentials(
+    conn: &rusqlite::Connection,
+    username: &str,
+    password: &str,
+) -> rusqlite::Result<Option<User>> {
+    let user = conn
+        .query_row(
+            "SELECT id, username, password_hash FROM users WHERE username = ?1",
+            rusqlite::params![username],
+            |row| {
+                Ok(User {
+                    id: row.get(0)?,
+                    username: row.get(1)?,
+                    password_hash: row.get(2)?,
+                })
+            },
+        )
+        .optional()?;
+
+    Ok(user.filter(|u| verify_password(password, &u.password_hash)))
+}
@@ -15,7 +15,7 @@ use commands::agents::{
     get_live_session_output, get_session_output, get_session_status, import_agent,
     import_agent_from_file, import_agent_from_github, init_database, kill_agent_session,
     list_agent_runs, list_agent_runs_with_metrics, list_agents, list_claude_installations,
-    list_running_sessions, load_agent_session_history, set_claude_binary_path, stream_session_output, update_agent, AgentDb,
+    list_running_sessions, load_agent_session_history, set_claude_binary_path, stream_session_output, update_agent,
 };
 use commands::claude::{
     cancel_claude_execution, check_auto_checkpoint, check_claude_version, cleanup_old_checkpoints,
@@ -44,8 +44,8 @@ use commands::storage::{
     storage_insert_row, storage_execute_sql, storage_reset_database,
 };
 use commands::proxy::{get_proxy_settings, save_proxy_settings, apply_proxy_settings};
+use rand::RngCore;
 use std::env;
-use std::sync::Mutex;
 // GUI-related imports commented out for API-only build
 // use std::path::PathBuf;
 // use tauri::Manager;
@@ -110,11 +110,28 @@ fn main() {
 
 fn handle_api_mode(args: &[String]) {
     println!("ðŸš€ Starting Opcode HTTP API server...");
-    
+
+    // Installs the process-wide Prometheus recorder `/metrics` renders.
+    // Must happen before the server (and anything that records a metric)
+    // starts, same ordering requirement as `env_logger::init()` above.
+    api::metrics::init_metrics();
+
     // Parse command line arguments for port
     let mut port = 3001u16;
+    let mut bind_host = "127.0.0.1".to_string();
+    let mut compression_level = tower_http::CompressionLevel::Default;
+    let mut tls_cert: Option<std::path::PathBuf> = None;
+    let mut tls_key: Option<std::path::PathBuf> = None;
+    let mut jwt_secret: Option<String> = None;
+    let mut disable_auth = false;
+    let mut db_pool_size = api::db::DEFAULT_POOL_SIZE;
+    let mut diagnostics_webhook_url: Option<String> = None;
+    let mut relay_enabled = false;
+    let mut relay_connect: Option<String> = None;
+    let mut relay_worker_id: Option<String> = None;
+    let mut relay_api_key: Option<String> = None;
     let mut i = 2; // Skip "opcode" and "api"
-    
+
     while i < args.len() {
         match args[i].as_str() {
             "--port" | "-p" => {
@@ -129,16 +146,158 @@ fn handle_api_mode(args: &[String]) {
                     std::process::exit(1);
                 }
             }
+            "--bind" => {
+                if i + 1 < args.len() {
+                    bind_host = args[i + 1].clone();
+                    i += 2;
+                } else {
+                    eprintln!("Error: --bind requires a host/IP");
+                    std::process::exit(1);
+                }
+            }
+            "--compression-level" => {
+                if i + 1 < args.len() {
+                    let level: i32 = args[i + 1].parse().unwrap_or_else(|_| {
+                        eprintln!("Error: Invalid compression level: {}", args[i + 1]);
+                        std::process::exit(1);
+                    });
+                    compression_level = tower_http::CompressionLevel::Precise(level);
+                    i += 2;
+                } else {
+                    eprintln!("Error: --compression-level requires a number");
+                    std::process::exit(1);
+                }
+            }
+            "--tls-cert" => {
+                if i + 1 < args.len() {
+                    tls_cert = Some(std::path::PathBuf::from(&args[i + 1]));
+                    i += 2;
+                } else {
+                    eprintln!("Error: --tls-cert requires a file path");
+                    std::process::exit(1);
+                }
+            }
+            "--tls-key" => {
+                if i + 1 < args.len() {
+                    tls_key = Some(std::path::PathBuf::from(&args[i + 1]));
+                    i += 2;
+                } else {
+                    eprintln!("Error: --tls-key requires a file path");
+                    std::process::exit(1);
+                }
+            }
+            "--jwt-secret" => {
+                if i + 1 < args.len() {
+                    jwt_secret = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    eprintln!("Error: --jwt-secret requires a value");
+                    std::process::exit(1);
+                }
+            }
+            "--disable-auth" => {
+                disable_auth = true;
+                i += 1;
+            }
+            "--db-pool-size" => {
+                if i + 1 < args.len() {
+                    db_pool_size = args[i + 1].parse().unwrap_or_else(|_| {
+                        eprintln!("Error: Invalid db pool size: {}", args[i + 1]);
+                        std::process::exit(1);
+                    });
+                    i += 2;
+                } else {
+                    eprintln!("Error: --db-pool-size requires a number");
+                    std::process::exit(1);
+                }
+            }
+            "--diagnostics-webhook-url" => {
+                if i + 1 < args.len() {
+                    diagnostics_webhook_url = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    eprintln!("Error: --diagnostics-webhook-url requires a value");
+                    std::process::exit(1);
+                }
+            }
+            "--relay" => {
+                relay_enabled = true;
+                i += 1;
+            }
+            "--relay-connect" => {
+                if i + 1 < args.len() {
+                    relay_connect = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    eprintln!("Error: --relay-connect requires a relay URL");
+                    std::process::exit(1);
+                }
+            }
+            "--relay-worker-id" => {
+                if i + 1 < args.len() {
+                    relay_worker_id = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    eprintln!("Error: --relay-worker-id requires an id");
+                    std::process::exit(1);
+                }
+            }
+            "--relay-api-key" => {
+                if i + 1 < args.len() {
+                    relay_api_key = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    eprintln!("Error: --relay-api-key requires a value");
+                    std::process::exit(1);
+                }
+            }
             _ => {
                 eprintln!("Error: Unknown argument: {}", args[i]);
-                eprintln!("Usage: opcode api [--port PORT]");
+                eprintln!("Usage: opcode api [--port PORT] [--bind HOST] [--compression-level LEVEL] [--tls-cert PATH --tls-key PATH] [--jwt-secret SECRET] [--disable-auth] [--db-pool-size SIZE] [--diagnostics-webhook-url URL] [--relay] [--relay-connect URL --relay-worker-id ID [--relay-api-key KEY]]");
                 std::process::exit(1);
             }
         }
     }
-    
-    println!("ðŸ“¡ Port: {}", port);
-    
+
+    // A worker dialing out needs an id to register under; a relay has no use
+    // for one of its own, so this is only required alongside --relay-connect.
+    if relay_connect.is_some() && relay_worker_id.is_none() {
+        eprintln!("Error: --relay-connect requires --relay-worker-id");
+        std::process::exit(1);
+    }
+
+    // Both --tls-cert and --tls-key must be given together; terminating TLS
+    // with only one half of the pair would silently fall back to plain HTTP.
+    let tls_config = match (tls_cert, tls_key) {
+        (Some(cert_path), Some(key_path)) => Some(api::TlsConfig { cert_path, key_path }),
+        (None, None) => None,
+        _ => {
+            eprintln!("Error: --tls-cert and --tls-key must be given together");
+            std::process::exit(1);
+        }
+    };
+
+    let bind_host: std::net::IpAddr = bind_host.parse().unwrap_or_else(|_| {
+        eprintln!("Error: Invalid --bind host/IP: {}", bind_host);
+        std::process::exit(1);
+    });
+
+    // Falls back to an env var so the secret can come from deployment
+    // config instead of a CLI flag that would show up in `ps`, and finally
+    // to a freshly generated one for local dev - tokens just won't survive
+    // a restart in that case.
+    let jwt_secret = jwt_secret
+        .or_else(|| std::env::var("OPCODE_JWT_SECRET").ok())
+        .unwrap_or_else(|| {
+            eprintln!("Warning: no --jwt-secret or OPCODE_JWT_SECRET set; generating a random one for this run only");
+            let mut bytes = [0u8; 32];
+            rand::thread_rng().fill_bytes(&mut bytes);
+            hex::encode(bytes)
+        });
+    let auth_enabled = !disable_auth;
+
+    println!("ðŸ“¡ Bind: {}:{}", bind_host, port);
+
     // Initialize the runtime for async operations
     let rt = tokio::runtime::Runtime::new().unwrap_or_else(|e| {
         eprintln!("Failed to create async runtime: {}", e);
@@ -155,7 +314,7 @@ fn handle_api_mode(args: &[String]) {
         
         // Initialize agents database directly
         let db_path = app_dir.join("agents.db");
-        let conn = rusqlite::Connection::open(db_path)
+        let conn = rusqlite::Connection::open(&db_path)
             .expect("Failed to open database");
         
         // Create tables manually (since init_database requires AppHandle)
@@ -206,21 +365,54 @@ fn handle_api_mode(args: &[String]) {
             )",
             [],
         ).expect("Failed to create app_settings table");
-        
-        let agent_db = AgentDb(Mutex::new(conn));
-        
+
+        api::auth::ensure_api_keys_table(&conn)
+            .expect("Failed to create api_keys table");
+        api::auth::bootstrap_admin_key_if_empty(&conn)
+            .expect("Failed to bootstrap admin API key");
+        api::errors::ensure_agent_run_errors_table(&conn)
+            .expect("Failed to create agent_run_errors table");
+        api::users::ensure_users_table(&conn)
+            .expect("Failed to create users table");
+        api::users::bootstrap_admin_user_if_empty(&conn)
+            .expect("Failed to bootstrap admin login account");
+
+        // `conn` only exists to run the one-time table creation above; the
+        // API server itself talks to the same file through a pool of its
+        // own connections instead of this single one.
+        drop(conn);
+        let db_pool = api::db::create_pool(&db_path, db_pool_size)
+            .expect("Failed to build database connection pool");
+
         // Initialize other states
         let checkpoint_state = CheckpointState::new();
         let process_registry = ProcessRegistryState::default();
-        
+
         // Create and start the API server
-        let api_server = api::ApiServer::new(
-            agent_db,
+        let api_server = api::ApiServer::with_tls(
+            db_pool,
             checkpoint_state,
             process_registry,
             Some(port),
+            Some(bind_host),
+            Some(compression_level),
+            tls_config,
+            jwt_secret,
+            auth_enabled,
+            diagnostics_webhook_url,
+            relay_enabled,
         );
-        
+
+        // Servicing forwarded requests only needs this instance's own
+        // router, so the worker connection is spawned alongside the normal
+        // listener rather than replacing it - the same process can still be
+        // reached directly.
+        if let Some(relay_url) = relay_connect {
+            let worker_id = relay_worker_id.expect("validated above");
+            let worker_app = api_server.get_app_for_testing();
+            tokio::spawn(api::relay::run_worker(relay_url, worker_id, relay_api_key, worker_app));
+        }
+
         if let Err(e) = api_server.start().await {
             eprintln!("Failed to start API server: {}", e);
             std::process::exit(1);
@@ -0,0 +1,244 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use hmac::{Hmac, Mac};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::AuthConfig;
+use crate::error::AppError;
+use crate::models::auth::{AuthPrincipal, TokenScope};
+use crate::services::DatabaseService;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The claims embedded in a JWT minted by [`TokenAuthenticator::issue_jwt`].
+/// `sub` is a caller-chosen identity label (not tied to any database row);
+/// `scope` mirrors [`TokenScope`] so JWT-authenticated callers are subject to
+/// the same per-route scope checks as database-backed tokens.
+#[derive(Debug, Serialize, Deserialize)]
+struct JwtClaims {
+    sub: String,
+    scope: String,
+    exp: u64,
+}
+
+/// A short-lived, HMAC-signed token that authorizes access to a single Claude
+/// Code session. The token binds the session id and an expiry so a token
+/// minted for one session can't be replayed against another.
+#[derive(Debug, Clone)]
+pub struct SessionToken {
+    pub session_id: String,
+    pub expires_at: u64,
+}
+
+/// Issues and validates `SessionToken`s using a shared HMAC secret.
+///
+/// The secret is the `auth.jwt_secret` value from `AppConfig`, reused here as
+/// the HMAC signing key rather than introducing a second secret to manage.
+#[derive(Clone)]
+pub struct TokenAuthenticator {
+    secret: String,
+}
+
+impl TokenAuthenticator {
+    pub fn new(secret: String) -> Self {
+        Self { secret }
+    }
+
+    /// Issue a token for `session_id` that expires `ttl_secs` seconds from now.
+    pub fn issue(&self, session_id: &str, ttl_secs: u64) -> String {
+        let expires_at = now_secs() + ttl_secs;
+        let payload = format!("{session_id}.{expires_at}");
+        let signature = self.sign(&payload);
+        format!("{payload}.{signature}")
+    }
+
+    /// Validate a token presented for `session_id`, rejecting it if the
+    /// signature doesn't match, the token has expired, or it was minted for
+    /// a different session.
+    pub fn validate(&self, session_id: &str, token: &str) -> bool {
+        let mut parts = token.rsplitn(2, '.');
+        let signature = match parts.next() {
+            Some(s) => s,
+            None => return false,
+        };
+        let payload = match parts.next() {
+            Some(p) => p,
+            None => return false,
+        };
+
+        let expected_signature = self.sign(payload);
+        if !constant_time_eq(signature.as_bytes(), expected_signature.as_bytes()) {
+            return false;
+        }
+
+        let mut payload_parts = payload.splitn(2, '.');
+        let token_session_id = match payload_parts.next() {
+            Some(s) => s,
+            None => return false,
+        };
+        let expires_at: u64 = match payload_parts.next().and_then(|v| v.parse().ok()) {
+            Some(v) => v,
+            None => return false,
+        };
+
+        token_session_id == session_id && now_secs() <= expires_at
+    }
+
+    /// Validate a raw shared-secret bearer token, used to gate the
+    /// session-management endpoints rather than a single WebSocket session.
+    pub fn validate_server_secret(&self, provided: &str) -> bool {
+        constant_time_eq(provided.as_bytes(), self.secret.as_bytes())
+    }
+
+    /// Mint a JWT identifying `name` with `scope`, signed with this
+    /// authenticator's secret and expiring `ttl_secs` seconds from now.
+    pub fn issue_jwt(&self, name: &str, scope: TokenScope, ttl_secs: u64) -> Result<String, jsonwebtoken::errors::Error> {
+        let claims = JwtClaims {
+            sub: name.to_string(),
+            scope: scope.as_str().to_string(),
+            exp: now_secs() + ttl_secs,
+        };
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(self.secret.as_bytes()),
+        )
+    }
+
+    /// Validate a bearer token as a JWT minted by [`Self::issue_jwt`],
+    /// returning the identity it carries if the signature and expiry check
+    /// out. Returns `None` for anything that isn't a validly-signed JWT,
+    /// letting the caller fall back to other auth mechanisms.
+    pub fn validate_jwt(&self, token: &str) -> Option<AuthPrincipal> {
+        let data = decode::<JwtClaims>(
+            token,
+            &DecodingKey::from_secret(self.secret.as_bytes()),
+            &Validation::default(),
+        )
+        .ok()?;
+
+        Some(AuthPrincipal {
+            // JWT-authenticated callers aren't backed by an `api_tokens` row.
+            token_id: 0,
+            name: data.claims.sub,
+            scope: TokenScope::parse(&data.claims.scope).unwrap_or(TokenScope::ReadOnly),
+        })
+    }
+
+    fn sign(&self, payload: &str) -> String {
+        let mut mac = HmacSha256::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC can take a key of any size");
+        mac.update(payload.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+}
+
+/// State the [`require_api_auth`] middleware needs: where to look up
+/// tokens, and whether/how auth is enforced.
+#[derive(Clone)]
+pub struct ApiAuthState {
+    pub db: Arc<DatabaseService>,
+    pub config: AuthConfig,
+    pub authenticator: TokenAuthenticator,
+}
+
+/// Gate the `/api/*` routers behind one of three credentials, checked in
+/// order: a static key from `config.api_keys` (via `Authorization: Bearer`
+/// or `x-api-key`), a JWT signed with `config.jwt_secret` (minted by
+/// [`TokenAuthenticator::issue_jwt`]), or a bearer token persisted via
+/// `DatabaseService`. Whichever one validates attaches its identity to the
+/// request's extensions as an [`AuthPrincipal`] so downstream handlers can
+/// enforce per-route scopes; presenting none of them is rejected with
+/// [`AppError::Unauthorized`].
+///
+/// No-ops entirely when `config.enabled` is `false`, and when
+/// `config.allow_local_bypass` is `true` also no-ops for requests from a
+/// loopback address, to keep the desktop use case token-free by default.
+pub async fn require_api_auth(
+    State(state): State<ApiAuthState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if !state.config.enabled {
+        return Ok(next.run(request).await);
+    }
+
+    if state.config.allow_local_bypass {
+        if let Some(ConnectInfo(addr)) = connect_info {
+            if addr.ip().is_loopback() {
+                return Ok(next.run(request).await);
+            }
+        }
+    }
+
+    let api_key_header = request
+        .headers()
+        .get("x-api-key")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    let bearer = request
+        .headers()
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string);
+
+    if let Some(candidate) = api_key_header.as_deref().or(bearer.as_deref()) {
+        if state
+            .config
+            .api_keys
+            .iter()
+            .any(|key| constant_time_eq(candidate.as_bytes(), key.as_bytes()))
+        {
+            request.extensions_mut().insert(AuthPrincipal {
+                token_id: 0,
+                name: "static-api-key".to_string(),
+                scope: TokenScope::ServerManagement,
+            });
+            return Ok(next.run(request).await);
+        }
+    }
+
+    let Some(token) = bearer else {
+        return Err(AppError::Unauthorized);
+    };
+
+    if let Some(principal) = state.authenticator.validate_jwt(&token) {
+        request.extensions_mut().insert(principal);
+        return Ok(next.run(request).await);
+    }
+
+    let principal = state
+        .db
+        .validate_api_token(&token)
+        .await
+        .map_err(|e| AppError::InternalError(e.to_string()))?
+        .ok_or(AppError::Unauthorized)?;
+
+    request.extensions_mut().insert(principal);
+    Ok(next.run(request).await)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -5,8 +5,12 @@ use axum::{
 };
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The JSON body every `AppError` is rendered as: `{"error": ApiError}`,
+/// with `code` a stable machine-readable string (e.g. `NOT_FOUND`) and
+/// `details` carrying variant-specific context for programmatic handling.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ApiError {
     pub code: String,
     pub message: String,
@@ -25,6 +29,7 @@ pub enum AppError {
     InternalError(String),
     Unauthorized,
     Forbidden,
+    Conflict { resource: String, message: String },
 }
 
 impl fmt::Display for AppError {
@@ -43,6 +48,9 @@ impl fmt::Display for AppError {
             AppError::InternalError(e) => write!(f, "Internal error: {}", e),
             AppError::Unauthorized => write!(f, "Unauthorized"),
             AppError::Forbidden => write!(f, "Forbidden"),
+            AppError::Conflict { resource, message } => {
+                write!(f, "{} conflict: {}", resource, message)
+            }
         }
     }
 }
@@ -106,6 +114,12 @@ impl IntoResponse for AppError {
                 "Access denied".to_string(),
                 None,
             ),
+            AppError::Conflict { resource, message } => (
+                StatusCode::CONFLICT,
+                format!("{}_CONFLICT", resource.to_uppercase().replace(' ', "_")),
+                self.to_string(),
+                Some(serde_json::json!({ "resource": resource, "message": message })),
+            ),
         };
 
         let api_error = ApiError {
@@ -144,4 +158,10 @@ impl From<config::ConfigError> for AppError {
     }
 }
 
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        AppError::InternalError(err.to_string())
+    }
+}
+
 pub type Result<T> = std::result::Result<T, AppError>;
\ No newline at end of file
@@ -0,0 +1,141 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{delete, get, post},
+    Router,
+};
+use std::sync::Arc;
+
+use crate::auth::TokenAuthenticator;
+use crate::config::AuthConfig;
+use crate::error::{AppError, Result};
+use crate::models::auth::{
+    ApiToken, IssueJwtRequest, IssueJwtResult, IssueTokenRequest, IssueTokenResult, TokenScope,
+};
+use crate::services::DatabaseService;
+
+/// State shared by the auth handlers: token CRUD goes straight to the
+/// database, while JWT issuance needs the shared signing secret and the
+/// configured static API keys it's exchanged against.
+#[derive(Clone)]
+pub struct AuthState {
+    pub db: Arc<DatabaseService>,
+    pub authenticator: TokenAuthenticator,
+    pub config: AuthConfig,
+}
+
+pub fn auth_router() -> Router<AuthState> {
+    Router::new()
+        .route("/auth/tokens", get(list_tokens).post(issue_token))
+        .route("/auth/tokens/:id", delete(revoke_token))
+        .route("/auth/jwt", post(issue_jwt))
+}
+
+/// List issued API tokens (metadata only; the raw token is never stored).
+#[utoipa::path(
+    get,
+    path = "/api/auth/tokens",
+    responses(
+        (status = 200, description = "List of API tokens", body = [ApiToken])
+    ),
+    tag = "auth"
+)]
+pub async fn list_tokens(State(state): State<AuthState>) -> Result<Json<Vec<ApiToken>>> {
+    let tokens = state.db.list_api_tokens().await?;
+    Ok(Json(tokens))
+}
+
+/// Mint a new API token. The raw token is only ever returned in this
+/// response; store it now, it can't be recovered later.
+#[utoipa::path(
+    post,
+    path = "/api/auth/tokens",
+    request_body = IssueTokenRequest,
+    responses(
+        (status = 201, description = "Token issued successfully", body = IssueTokenResult),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "auth"
+)]
+pub async fn issue_token(
+    State(state): State<AuthState>,
+    Json(request): Json<IssueTokenRequest>,
+) -> Result<(StatusCode, Json<IssueTokenResult>)> {
+    let (id, token) = state.db.create_api_token(&request.name, request.scope).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(IssueTokenResult {
+            id,
+            name: request.name,
+            scope: request.scope,
+            token,
+        }),
+    ))
+}
+
+/// Revoke a token by id. A revoked token is kept (for audit) but no longer
+/// validates.
+#[utoipa::path(
+    delete,
+    path = "/api/auth/tokens/{id}",
+    params(
+        ("id" = i64, Path, description = "Token ID")
+    ),
+    responses(
+        (status = 204, description = "Token revoked successfully"),
+        (status = 404, description = "Token not found")
+    ),
+    tag = "auth"
+)]
+pub async fn revoke_token(
+    State(state): State<AuthState>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode> {
+    if state.db.revoke_api_token(id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(crate::error::AppError::NotFound {
+            resource: "API token".to_string(),
+            id: id.to_string(),
+        })
+    }
+}
+
+/// Exchange a static API key for a short-lived JWT, so callers that only
+/// hold a long-lived key can mint a scoped, expiring credential instead of
+/// sending the key itself on every request.
+#[utoipa::path(
+    post,
+    path = "/api/auth/jwt",
+    request_body = IssueJwtRequest,
+    responses(
+        (status = 200, description = "JWT issued successfully", body = IssueJwtResult),
+        (status = 401, description = "API key not recognized")
+    ),
+    tag = "auth"
+)]
+pub async fn issue_jwt(
+    State(state): State<AuthState>,
+    Json(request): Json<IssueJwtRequest>,
+) -> Result<Json<IssueJwtResult>> {
+    let known = state
+        .config
+        .api_keys
+        .iter()
+        .any(|key| key == &request.api_key);
+
+    if !known {
+        return Err(AppError::Unauthorized);
+    }
+
+    let token = state
+        .authenticator
+        .issue_jwt("static-api-key", TokenScope::ServerManagement, state.config.token_expiry)
+        .map_err(|e| AppError::InternalError(e.to_string()))?;
+
+    Ok(Json(IssueJwtResult {
+        token,
+        expires_in: state.config.token_expiry,
+    }))
+}
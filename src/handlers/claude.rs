@@ -1,22 +1,41 @@
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
     response::Json,
     routing::{get, post, put, delete},
-    Router,
+    Extension, Router,
 };
+use futures::stream::StreamExt;
+use std::convert::Infallible;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 use serde::Deserialize;
+use tokio_stream::{wrappers::BroadcastStream, Stream};
 
+use crate::middleware::RequestId;
+use crate::models::auth::{AuthPrincipal, TokenScope};
 use crate::models::claude::{Project, StartSessionRequest, SessionRecord, CreateProjectRequest, UpdateProjectRequest};
 use crate::services::{ClaudeService, DatabaseService};
 
-pub fn claude_router() -> Router<Arc<DatabaseService>> {
+/// State shared by the Claude handlers: project CRUD goes straight to the
+/// database, while session lifecycle goes through `ClaudeService` so its
+/// session supervisor stays the single source of truth for what's running.
+#[derive(Clone)]
+pub struct ClaudeState {
+    pub db: Arc<DatabaseService>,
+    pub claude: Arc<ClaudeService>,
+}
+
+pub fn claude_router() -> Router<ClaudeState> {
     Router::new()
         .route("/claude/projects", get(list_projects).post(create_project))
         .route("/claude/projects/:id", put(update_project).delete(delete_project))
         .route("/claude/sessions", get(list_sessions).post(start_session))
         .route("/claude/sessions/:id", get(get_session))
+        .route("/claude/sessions/:id/stream", get(stream_session_output))
+        .route("/claude/sessions/:id/cancel", post(cancel_session))
 }
 
 #[derive(Deserialize)]
@@ -24,7 +43,7 @@ pub struct SessionsQuery {
     project_path: Option<String>,
 }
 
-/// List all Claude projects  
+/// List all Claude projects
 #[utoipa::path(
     get,
     path = "/api/claude/projects",
@@ -33,9 +52,9 @@ pub struct SessionsQuery {
     )
 )]
 pub async fn list_projects(
-    State(db): State<Arc<DatabaseService>>,
+    State(state): State<ClaudeState>,
 ) -> Result<Json<Vec<Project>>, StatusCode> {
-    match db.get_projects() {
+    match state.db.get_projects().await {
         Ok(projects) => Ok(Json(projects)),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
@@ -53,14 +72,14 @@ pub async fn list_projects(
     )
 )]
 pub async fn create_project(
-    State(db): State<Arc<DatabaseService>>,
+    State(state): State<ClaudeState>,
     Json(request): Json<CreateProjectRequest>,
 ) -> Result<(StatusCode, Json<Project>), StatusCode> {
-    match db.create_project(request) {
+    match state.db.create_project(request).await {
         Ok(project) => Ok((StatusCode::CREATED, Json(project))),
         Err(e) => {
             let error_msg = e.to_string();
-            if error_msg.contains("Parent directory does not exist") || 
+            if error_msg.contains("Parent directory does not exist") ||
                error_msg.contains("Invalid path") ||
                error_msg.contains("UNIQUE constraint failed") {
                 Err(StatusCode::BAD_REQUEST)
@@ -84,10 +103,10 @@ pub async fn create_project(
     )
 )]
 pub async fn list_sessions(
-    State(db): State<Arc<DatabaseService>>,
+    State(state): State<ClaudeState>,
     Query(params): Query<SessionsQuery>,
 ) -> Result<Json<Vec<SessionRecord>>, StatusCode> {
-    match db.get_sessions(params.project_path.as_deref()) {
+    match state.claude.list_sessions(params.project_path.as_deref()).await {
         Ok(sessions) => Ok(Json(sessions)),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
@@ -106,10 +125,10 @@ pub async fn list_sessions(
     )
 )]
 pub async fn get_session(
-    State(db): State<Arc<DatabaseService>>,
+    State(state): State<ClaudeState>,
     Path(session_id): Path<String>,
 ) -> Result<Json<SessionRecord>, StatusCode> {
-    match db.get_session(&session_id) {
+    match state.claude.get_session(&session_id).await {
         Ok(Some(session)) => Ok(Json(session)),
         Ok(None) => Err(StatusCode::NOT_FOUND),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
@@ -119,7 +138,7 @@ pub async fn get_session(
 /// Start a new Claude session
 #[utoipa::path(
     post,
-    path = "/api/claude/sessions", 
+    path = "/api/claude/sessions",
     request_body = StartSessionRequest,
     responses(
         (status = 201, description = "Session started successfully", body = String),
@@ -128,26 +147,81 @@ pub async fn get_session(
     )
 )]
 pub async fn start_session(
-    State(db): State<Arc<DatabaseService>>,
+    principal: Option<Extension<AuthPrincipal>>,
+    request_id: Option<Extension<RequestId>>,
+    State(state): State<ClaudeState>,
     Json(request): Json<StartSessionRequest>,
 ) -> Result<(StatusCode, Json<serde_json::Value>), StatusCode> {
-    let session_id = uuid::Uuid::new_v4().to_string();
-    
-    // Store session record in database
-    if let Err(e) = db.create_session_record(
-        &session_id,
-        &request.prompt,
-        &request.project_path,
-        &request.model.unwrap_or_else(|| "claude-3-5-sonnet-20241022".to_string())
-    ) {
-        tracing::error!("Failed to create session record: {}", e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    if let Some(Extension(p)) = &principal {
+        if p.scope != TokenScope::ServerManagement {
+            return Err(StatusCode::FORBIDDEN);
+        }
+    }
+
+    let request_id = request_id.map(|Extension(id)| id.0);
+
+    match state.claude.start_session(request, request_id).await {
+        Ok(session_id) => Ok((
+            StatusCode::CREATED,
+            Json(serde_json::json!({ "session_id": session_id })),
+        )),
+        Err(e) => {
+            tracing::error!("Failed to start session: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
     }
-    
-    Ok((
-        StatusCode::CREATED,
-        Json(serde_json::json!({ "session_id": session_id })),
-    ))
+}
+
+/// Stream a running session's live output as Server-Sent Events. Closes
+/// immediately with no events if the session isn't currently supervised
+/// (already finished, or never started on this server).
+#[utoipa::path(
+    get,
+    path = "/api/claude/sessions/{id}/stream",
+    params(
+        ("id" = String, Path, description = "Session ID")
+    ),
+    responses(
+        (status = 200, description = "Live session output stream")
+    )
+)]
+pub async fn stream_session_output(
+    State(state): State<ClaudeState>,
+    Path(session_id): Path<String>,
+) -> Sse<Pin<Box<dyn Stream<Item = Result<Event, Infallible>> + Send>>> {
+    let stream: Pin<Box<dyn Stream<Item = Result<Event, Infallible>> + Send>> =
+        match state.claude.subscribe_output(&session_id).await {
+            Some(receiver) => Box::pin(
+                BroadcastStream::new(receiver)
+                    .filter_map(|line| async move { line.ok().map(|line| Ok(Event::default().data(line))) }),
+            ),
+            None => Box::pin(futures::stream::empty()),
+        };
+
+    Sse::new(stream).keep_alive(
+        KeepAlive::new()
+            .interval(Duration::from_secs(15))
+            .text("keep-alive"),
+    )
+}
+
+/// Cancel a currently-running session's process.
+#[utoipa::path(
+    post,
+    path = "/api/claude/sessions/{id}/cancel",
+    params(
+        ("id" = String, Path, description = "Session ID")
+    ),
+    responses(
+        (status = 200, description = "Cancellation result")
+    )
+)]
+pub async fn cancel_session(
+    State(state): State<ClaudeState>,
+    Path(session_id): Path<String>,
+) -> Json<serde_json::Value> {
+    let cancelled = state.claude.cancel_session(&session_id).await;
+    Json(serde_json::json!({ "session_id": session_id, "cancelled": cancelled }))
 }
 
 /// Update an existing project
@@ -165,11 +239,11 @@ pub async fn start_session(
     )
 )]
 pub async fn update_project(
-    State(db): State<Arc<DatabaseService>>,
+    State(state): State<ClaudeState>,
     Path(project_id): Path<String>,
     Json(request): Json<UpdateProjectRequest>,
 ) -> Result<Json<Project>, StatusCode> {
-    match db.update_project(&project_id, request) {
+    match state.db.update_project(&project_id, request).await {
         Ok(Some(project)) => Ok(Json(project)),
         Ok(None) => Err(StatusCode::NOT_FOUND),
         Err(e) => {
@@ -193,10 +267,10 @@ pub async fn update_project(
     )
 )]
 pub async fn delete_project(
-    State(db): State<Arc<DatabaseService>>,
+    State(state): State<ClaudeState>,
     Path(project_id): Path<String>,
 ) -> Result<StatusCode, StatusCode> {
-    match db.delete_project(&project_id) {
+    match state.db.delete_project(&project_id).await {
         Ok(true) => Ok(StatusCode::NO_CONTENT),
         Ok(false) => Err(StatusCode::NOT_FOUND),
         Err(e) => {
@@ -204,4 +278,4 @@ pub async fn delete_project(
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
-}
\ No newline at end of file
+}
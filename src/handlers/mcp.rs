@@ -3,17 +3,30 @@ use axum::{
     http::StatusCode,
     response::Json,
     routing::{delete, get, post, put},
-    Router,
+    Extension, Router,
 };
 use std::sync::Arc;
 
-use crate::error::Result;
+use crate::error::{ApiError, AppError, Result};
+use crate::models::auth::{AuthPrincipal, TokenScope};
 use crate::models::mcp::{
     AddMCPServerRequest, ConnectionTestResult, ImportResult, MCPServer, MCPServerResult,
     UpdateMCPServerRequest,
 };
 use crate::services::MCPService;
 
+/// Reject the request unless the caller's token carries `ServerManagement`
+/// scope. A missing principal (auth disabled or locally bypassed) is treated
+/// as implicitly authorized, matching `require_api_auth`'s own no-op modes.
+fn require_management_scope(principal: &Option<Extension<AuthPrincipal>>) -> Result<()> {
+    match principal {
+        Some(Extension(p)) if p.scope != TokenScope::ServerManagement => {
+            Err(AppError::Forbidden)
+        }
+        _ => Ok(()),
+    }
+}
+
 pub fn mcp_router() -> Router<Arc<MCPService>> {
     Router::new()
         .route("/mcp/servers", get(list_servers))
@@ -34,7 +47,7 @@ pub fn mcp_router() -> Router<Arc<MCPService>> {
     ),
     tag = "mcp"
 )]
-async fn list_servers(State(mcp): State<Arc<MCPService>>) -> Result<Json<Vec<MCPServer>>> {
+pub async fn list_servers(State(mcp): State<Arc<MCPService>>) -> Result<Json<Vec<MCPServer>>> {
     let servers = mcp.list_servers().await?;
     Ok(Json(servers))
 }
@@ -48,11 +61,11 @@ async fn list_servers(State(mcp): State<Arc<MCPService>>) -> Result<Json<Vec<MCP
     ),
     responses(
         (status = 200, description = "MCP server details", body = MCPServer),
-        (status = 404, description = "Server not found")
+        (status = 404, description = "Server not found", body = ApiError)
     ),
     tag = "mcp"
 )]
-async fn get_server(
+pub async fn get_server(
     Path(name): Path<String>,
     State(mcp): State<Arc<MCPService>>,
 ) -> Result<Json<MCPServer>> {
@@ -67,15 +80,19 @@ async fn get_server(
     request_body = AddMCPServerRequest,
     responses(
         (status = 201, description = "Server added successfully", body = MCPServerResult),
-        (status = 400, description = "Invalid request"),
-        (status = 500, description = "Internal server error")
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 403, description = "Caller's token lacks server-management scope", body = ApiError),
+        (status = 409, description = "A server with this name already exists", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
     ),
     tag = "mcp"
 )]
-async fn add_server(
+pub async fn add_server(
+    principal: Option<Extension<AuthPrincipal>>,
     State(mcp): State<Arc<MCPService>>,
     Json(request): Json<AddMCPServerRequest>,
 ) -> Result<(StatusCode, Json<MCPServerResult>)> {
+    require_management_scope(&principal)?;
     let result = mcp.add_server(request).await?;
     Ok((StatusCode::CREATED, Json(result)))
 }
@@ -90,17 +107,20 @@ async fn add_server(
     request_body = UpdateMCPServerRequest,
     responses(
         (status = 200, description = "Server updated successfully", body = MCPServerResult),
-        (status = 404, description = "Server not found"),
-        (status = 400, description = "Invalid request"),
-        (status = 500, description = "Internal server error")
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 403, description = "Caller's token lacks server-management scope", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
     ),
     tag = "mcp"
 )]
-async fn update_server(
+pub async fn update_server(
+    principal: Option<Extension<AuthPrincipal>>,
     Path(name): Path<String>,
     State(mcp): State<Arc<MCPService>>,
     Json(request): Json<UpdateMCPServerRequest>,
 ) -> Result<Json<MCPServerResult>> {
+    require_management_scope(&principal)?;
     let result = mcp.update_server(&name, request).await?;
     Ok(Json(result))
 }
@@ -114,15 +134,18 @@ async fn update_server(
     ),
     responses(
         (status = 200, description = "Server removed successfully", body = MCPServerResult),
-        (status = 404, description = "Server not found"),
-        (status = 500, description = "Internal server error")
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 403, description = "Caller's token lacks server-management scope", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
     ),
     tag = "mcp"
 )]
-async fn remove_server(
+pub async fn remove_server(
+    principal: Option<Extension<AuthPrincipal>>,
     Path(name): Path<String>,
     State(mcp): State<Arc<MCPService>>,
 ) -> Result<Json<MCPServerResult>> {
+    require_management_scope(&principal)?;
     let result = mcp.remove_server(&name).await?;
     Ok(Json(result))
 }
@@ -136,12 +159,12 @@ async fn remove_server(
     ),
     responses(
         (status = 200, description = "Connection test result", body = ConnectionTestResult),
-        (status = 404, description = "Server not found"),
-        (status = 500, description = "Internal server error")
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
     ),
     tag = "mcp"
 )]
-async fn test_connection(
+pub async fn test_connection(
     Path(name): Path<String>,
     State(mcp): State<Arc<MCPService>>,
 ) -> Result<Json<ConnectionTestResult>> {
@@ -155,13 +178,16 @@ async fn test_connection(
     path = "/api/mcp/import",
     responses(
         (status = 200, description = "Import completed", body = ImportResult),
-        (status = 500, description = "Internal server error")
+        (status = 403, description = "Caller's token lacks server-management scope", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
     ),
     tag = "mcp"
 )]
-async fn import_from_claude_desktop(
+pub async fn import_from_claude_desktop(
+    principal: Option<Extension<AuthPrincipal>>,
     State(mcp): State<Arc<MCPService>>,
 ) -> Result<Json<ImportResult>> {
+    require_management_scope(&principal)?;
     let result = mcp.import_from_claude_desktop().await?;
     Ok(Json(result))
 }
\ No newline at end of file
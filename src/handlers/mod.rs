@@ -1,9 +1,11 @@
 pub mod agents;
+pub mod auth;
 pub mod claude;
 pub mod mcp;
 pub mod storage;
 
 pub use agents::agents_router;
+pub use auth::auth_router;
 pub use claude::claude_router;
 pub use mcp::mcp_router;
 pub use storage::storage_router;
\ No newline at end of file
@@ -1,73 +1,233 @@
 use axum::{
+    extract::{Query, State},
     http::StatusCode,
     response::Json,
     routing::get,
     Router,
 };
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
 use walkdir::WalkDir;
 
-pub fn storage_router() -> Router {
-    Router::new()
-        .route("/storage/usage", get(get_storage_usage))
+use crate::models::{CategoryStorageUsage, ProjectStorageUsage, StorageUsage};
+
+/// How long a computed [`StorageUsage`] snapshot stays valid before a plain
+/// (non-`refresh`) request triggers a recompute. Dashboard polling is the
+/// main caller, so this just needs to be short enough that a user action
+/// (new session, checkpoint) shows up without a page reload, not so short
+/// that repeated polling keeps rescanning `~/.claude`.
+const CACHE_TTL: Duration = Duration::from_secs(60);
+
+struct CachedUsage {
+    computed_at: std::time::Instant,
+    usage: StorageUsage,
+}
+
+/// Shared state for the storage handlers: a single-slot cache guarded by a
+/// `Mutex`, mirroring how `WebSocketManager` caches the resolved Claude
+/// binary elsewhere in this crate.
+#[derive(Clone)]
+pub struct StorageState {
+    cache: Arc<Mutex<Option<CachedUsage>>>,
+}
+
+impl StorageState {
+    pub fn new() -> Self {
+        Self {
+            cache: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+impl Default for StorageState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn storage_router() -> Router<StorageState> {
+    Router::new().route("/storage/usage", get(get_storage_usage))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StorageUsageQuery {
+    /// Force a recompute instead of serving a cached snapshot, regardless
+    /// of how fresh it still is.
+    #[serde(default)]
+    refresh: bool,
 }
 
 /// Get storage usage statistics
 #[utoipa::path(
     get,
     path = "/api/storage/usage",
+    params(
+        ("refresh" = Option<bool>, Query, description = "Force a fresh scan instead of serving a cached snapshot")
+    ),
     responses(
-        (status = 200, description = "Storage usage statistics", body = serde_json::Value)
-    )
+        (status = 200, description = "Storage usage statistics", body = StorageUsage)
+    ),
+    tag = "storage"
 )]
-async fn get_storage_usage() -> Result<Json<serde_json::Value>, StatusCode> {
-    // Calculate storage usage from ~/.claude directory
+pub async fn get_storage_usage(
+    State(state): State<StorageState>,
+    Query(query): Query<StorageUsageQuery>,
+) -> Result<Json<StorageUsage>, StatusCode> {
+    if !query.refresh {
+        let cache = state.cache.lock().await;
+        if let Some(cached) = cache.as_ref() {
+            if cached.computed_at.elapsed() < CACHE_TTL {
+                return Ok(Json(cached.usage.clone()));
+            }
+        }
+    }
+
+    // The walk itself runs on a blocking-pool thread - `~/.claude` can
+    // easily hold thousands of session files, and doing that synchronously
+    // in this async handler would stall the Tokio worker it runs on for
+    // every other request in flight.
+    let usage = tokio::task::spawn_blocking(compute_storage_usage)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut cache = state.cache.lock().await;
+    *cache = Some(CachedUsage {
+        computed_at: std::time::Instant::now(),
+        usage: usage.clone(),
+    });
+
+    Ok(Json(usage))
+}
+
+fn compute_storage_usage() -> StorageUsage {
+    let computed_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
     let claude_dir = match dirs::home_dir() {
         Some(home) => home.join(".claude"),
-        None => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+        None => {
+            return empty_usage(computed_at);
+        }
     };
 
     if !claude_dir.exists() {
-        return Ok(Json(serde_json::json!({
-            "total_size_bytes": 0,
-            "total_files": 0,
-            "projects_count": 0,
-            "sessions_count": 0
-        })));
+        return empty_usage(computed_at);
     }
 
     let mut total_size = 0u64;
     let mut total_files = 0usize;
-    let mut projects_count = 0usize;
     let mut sessions_count = 0usize;
+    let mut projects: HashMap<String, ProjectStorageUsage> = HashMap::new();
+    let mut checkpoints = CategoryStorageUsage { files: 0, size_bytes: 0 };
+    let mut todos = CategoryStorageUsage { files: 0, size_bytes: 0 };
+    let mut ide = CategoryStorageUsage { files: 0, size_bytes: 0 };
+
+    let projects_dir = claude_dir.join("projects");
+
+    for entry in WalkDir::new(&claude_dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+    {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let path = entry.path();
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        let modified = entry
+            .metadata()
+            .ok()
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
+        let is_session = path.extension().and_then(|ext| ext.to_str()) == Some("jsonl");
+
+        total_files += 1;
+        total_size += size;
+        if is_session {
+            sessions_count += 1;
+        }
 
-    // Walk through the .claude directory
-    if let Ok(walker) = WalkDir::new(&claude_dir).into_iter().collect::<Result<Vec<_>, _>>() {
-        for entry in walker {
-            if entry.file_type().is_file() {
-                total_files += 1;
-                if let Ok(metadata) = entry.metadata() {
-                    total_size += metadata.len();
-                }
-                
-                // Count sessions (JSONL files)
-                if entry.path().extension().and_then(|ext| ext.to_str()) == Some("jsonl") {
-                    sessions_count += 1;
-                }
-            } else if entry.file_type().is_dir() {
-                // Count projects (directories in projects/)
-                if let Some(parent) = entry.path().parent() {
-                    if parent.file_name().and_then(|name| name.to_str()) == Some("projects") {
-                        projects_count += 1;
-                    }
-                }
+        if let Some(project_name) = project_dir_name(path, &projects_dir) {
+            let project = projects
+                .entry(project_name.clone())
+                .or_insert_with(|| ProjectStorageUsage {
+                    path: project_name,
+                    size_bytes: 0,
+                    sessions_count: 0,
+                    most_recent_modified: None,
+                });
+            project.size_bytes += size;
+            if is_session {
+                project.sessions_count += 1;
             }
+            project.most_recent_modified = match (project.most_recent_modified, modified) {
+                (Some(current), Some(candidate)) => Some(current.max(candidate)),
+                (current, None) => current,
+                (None, Some(candidate)) => Some(candidate),
+            };
+        } else if path_has_component(path, "checkpoints") {
+            checkpoints.files += 1;
+            checkpoints.size_bytes += size;
+        } else if path_has_component(path, "todos") {
+            todos.files += 1;
+            todos.size_bytes += size;
+        } else if path_has_component(path, "ide") {
+            ide.files += 1;
+            ide.size_bytes += size;
         }
     }
 
-    Ok(Json(serde_json::json!({
-        "total_size_bytes": total_size,
-        "total_files": total_files,
-        "projects_count": projects_count,
-        "sessions_count": sessions_count
-    })))
-}
\ No newline at end of file
+    let projects_count = projects.len();
+    let mut projects: Vec<ProjectStorageUsage> = projects.into_values().collect();
+    projects.sort_by(|a, b| a.path.cmp(&b.path));
+
+    StorageUsage {
+        total_size_bytes: total_size,
+        total_files,
+        projects_count,
+        sessions_count,
+        projects,
+        checkpoints,
+        todos,
+        ide,
+        computed_at,
+    }
+}
+
+fn empty_usage(computed_at: u64) -> StorageUsage {
+    StorageUsage {
+        total_size_bytes: 0,
+        total_files: 0,
+        projects_count: 0,
+        sessions_count: 0,
+        projects: Vec::new(),
+        checkpoints: CategoryStorageUsage { files: 0, size_bytes: 0 },
+        todos: CategoryStorageUsage { files: 0, size_bytes: 0 },
+        ide: CategoryStorageUsage { files: 0, size_bytes: 0 },
+        computed_at,
+    }
+}
+
+/// Returns the project directory's name (the immediate child of
+/// `projects_dir` that `path` lives under) if `path` is inside it at all.
+fn project_dir_name(path: &Path, projects_dir: &Path) -> Option<String> {
+    path.strip_prefix(projects_dir)
+        .ok()
+        .and_then(|rel| rel.components().next())
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+}
+
+/// Whether any component of `path` is exactly `name` - used to bucket files
+/// under `~/.claude/<name>/...` into their own category regardless of how
+/// deep they're nested.
+fn path_has_component(path: &Path, name: &str) -> bool {
+    path.components()
+        .any(|component| component.as_os_str() == name)
+}
@@ -9,19 +9,28 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
+mod auth;
 mod config;
 mod error;
 mod handlers;
+mod middleware;
 mod models;
 mod services;
 mod websocket;
 
+use auth::{ApiAuthState, TokenAuthenticator};
 use config::AppConfig;
 use error::Result;
-use handlers::{agents_router, claude_router, mcp_router, storage_router};
+use handlers::claude::ClaudeState;
+use handlers::{agents_router, auth_router, claude_router, mcp_router, storage_router};
 use services::{ClaudeService, DatabaseService, MCPService};
+use std::sync::OnceLock;
 use websocket::{WebSocketManager, websocket_router};
 
+/// Keeps the non-blocking file writer's flush thread alive for the process
+/// lifetime; dropping the guard stops it from flushing buffered log lines.
+static LOG_GUARD: OnceLock<tracing_appender::non_blocking::WorkerGuard> = OnceLock::new();
+
 #[derive(OpenApi)]
 #[openapi(
     paths(
@@ -33,7 +42,20 @@ use websocket::{WebSocketManager, websocket_router};
         handlers::claude::list_sessions,
         handlers::claude::get_session,
         handlers::claude::start_session,
+        handlers::claude::stream_session_output,
+        handlers::claude::cancel_session,
         handlers::storage::get_storage_usage,
+        handlers::auth::list_tokens,
+        handlers::auth::issue_token,
+        handlers::auth::revoke_token,
+        handlers::auth::issue_jwt,
+        handlers::mcp::list_servers,
+        handlers::mcp::get_server,
+        handlers::mcp::add_server,
+        handlers::mcp::update_server,
+        handlers::mcp::remove_server,
+        handlers::mcp::test_connection,
+        handlers::mcp::import_from_claude_desktop,
     ),
     components(
         schemas(
@@ -42,12 +64,31 @@ use websocket::{WebSocketManager, websocket_router};
             models::claude::Project,
             models::claude::StartSessionRequest,
             models::claude::SessionRecord,
+            models::StorageUsage,
+            models::ProjectStorageUsage,
+            models::CategoryStorageUsage,
+            models::auth::ApiToken,
+            models::auth::IssueTokenRequest,
+            models::auth::IssueTokenResult,
+            models::auth::IssueJwtRequest,
+            models::auth::IssueJwtResult,
+            models::mcp::MCPServer,
+            models::mcp::ServerStatus,
+            models::mcp::AddMCPServerRequest,
+            models::mcp::UpdateMCPServerRequest,
+            models::mcp::MCPServerResult,
+            models::mcp::ImportResult,
+            models::mcp::ImportServerResult,
+            models::mcp::ConnectionTestResult,
+            error::ApiError,
         )
     ),
     tags(
         (name = "agents", description = "Agent management API"),
         (name = "claude", description = "Claude Code session management API"),
         (name = "storage", description = "Storage management API"),
+        (name = "auth", description = "API token issuance and management"),
+        (name = "mcp", description = "MCP server configuration and import API"),
     )
 )]
 struct ApiDoc;
@@ -64,34 +105,79 @@ async fn main() -> Result<()> {
     tracing::info!("Configuration loaded: {:#?}", config);
 
     // Initialize services
-    let db_service = Arc::new(DatabaseService::new()?);
-    let _claude_service = Arc::new(ClaudeService::new(db_service.clone())?);
-    let mcp_service = Arc::new(MCPService::new()?);
-    let ws_manager = Arc::new(WebSocketManager::new());
+    let db_service = Arc::new(DatabaseService::new(&config.database)?);
+    let claude_service = Arc::new(ClaudeService::new(db_service.clone())?);
+    let mcp_service = Arc::new(MCPService::new(db_service.clone())?);
+    let authenticator = TokenAuthenticator::new(config.auth.jwt_secret.clone());
+    let claude_binary_override = config
+        .claude
+        .binary_path
+        .clone()
+        .map(|p| p.to_string_lossy().into_owned());
+    let ws_manager = Arc::new(WebSocketManager::new(
+        authenticator.clone(),
+        claude_binary_override,
+    ));
+
+    // Resolve the Claude binary once at startup rather than on first use, so
+    // a missing/incompatible install is visible in the logs and via
+    // `/health` immediately instead of surfacing as the first request's error.
+    match ws_manager.resolve_claude_binary().await {
+        Ok(info) => tracing::info!(
+            "Using Claude binary {} (version {})",
+            info.path.display(),
+            info.version
+        ),
+        Err(e) => tracing::warn!("Claude binary not ready: {}", e),
+    }
 
     // Create API documentation
     let api_doc = ApiDoc::openapi();
 
     // Build the application router
+    let api_auth_state = ApiAuthState {
+        db: db_service.clone(),
+        config: config.auth.clone(),
+        authenticator: authenticator.clone(),
+    };
+    let auth_state = handlers::auth::AuthState {
+        db: db_service.clone(),
+        authenticator: authenticator.clone(),
+        config: config.auth.clone(),
+    };
+    let api_routes = Router::new()
+        .merge(agents_router().with_state(db_service.clone()))
+        .merge(auth_router().with_state(auth_state))
+        .merge(claude_router().with_state(ClaudeState {
+            db: db_service.clone(),
+            claude: claude_service.clone(),
+        }))
+        .merge(mcp_router().with_state(mcp_service.clone()))
+        .merge(storage_router().with_state(handlers::storage::StorageState::new()));
+
     let app = Router::new()
         // API routes
-        .nest("/api", 
-            Router::new()
-                .merge(agents_router().with_state(db_service.clone()))
-                .merge(claude_router().with_state(db_service.clone()))
-                .merge(mcp_router().with_state(mcp_service.clone()))
-                .merge(storage_router())
+        .nest("/api",
+            api_routes.layer(axum::middleware::from_fn_with_state(
+                api_auth_state,
+                auth::require_api_auth,
+            ))
         )
         // WebSocket routes
         .merge(websocket_router().with_state(ws_manager.clone()))
         // Health check
-        .route("/health", get(health_check))
+        .route("/health", get({
+            let ws_manager = ws_manager.clone();
+            move || health_check(ws_manager)
+        }))
         // Swagger UI
         .merge(SwaggerUi::new("/docs").url("/api-docs/openapi.json", api_doc))
         // Middleware
         .layer(
             ServiceBuilder::new()
-                .layer(TraceLayer::new_for_http())
+                .layer(axum::middleware::from_fn(middleware::request_id_middleware))
+                .layer(axum::middleware::from_fn(middleware::trace_completion_middleware))
+                .layer(TraceLayer::new_for_http().make_span_with(middleware::RequestIdMakeSpan))
                 .layer(CorsLayer::very_permissive())
         );
 
@@ -104,28 +190,58 @@ async fn main() -> Result<()> {
     tracing::info!("🏥 Health check at http://{}/health", addr);
     tracing::info!("🔌 WebSocket endpoints at ws://{}/ws/...", addr);
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await?;
 
     tracing::info!("Server shutdown complete");
     Ok(())
 }
 
+/// Builds the global `tracing` subscriber from `config.logging`: `level`
+/// becomes an `EnvFilter` (overridable via `RUST_LOG`), `json_format` picks
+/// between a human-readable and a JSON formatting layer, and, when `file` is
+/// set, logs go to a non-blocking file writer instead of the console.
 fn init_tracing(config: &AppConfig) -> Result<()> {
     let filter = tracing_subscriber::EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(&config.logging.level));
 
-    let subscriber = tracing_subscriber::registry().with(filter);
+    let registry = tracing_subscriber::registry().with(filter);
 
-    if config.logging.json_format {
-        subscriber
-            .with(tracing_subscriber::fmt::layer().json())
-            .init();
-    } else {
-        subscriber
-            .with(tracing_subscriber::fmt::layer())
-            .init();
+    match &config.logging.file {
+        Some(path) => {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| {
+                    error::AppError::InternalError(format!(
+                        "failed to open log file {}: {e}",
+                        path.display()
+                    ))
+                })?;
+            let (writer, guard) = tracing_appender::non_blocking(file);
+            let _ = LOG_GUARD.set(guard);
+
+            if config.logging.json_format {
+                registry
+                    .with(tracing_subscriber::fmt::layer().json().with_writer(writer))
+                    .init();
+            } else {
+                registry
+                    .with(tracing_subscriber::fmt::layer().with_writer(writer))
+                    .init();
+            }
+        }
+        None if config.logging.json_format => {
+            registry.with(tracing_subscriber::fmt::layer().json()).init();
+        }
+        None => {
+            registry.with(tracing_subscriber::fmt::layer()).init();
+        }
     }
 
     Ok(())
@@ -157,6 +273,14 @@ async fn shutdown_signal() {
     tracing::info!("Shutdown signal received");
 }
 
-async fn health_check() -> &'static str {
-    "OK"
+async fn health_check(ws_manager: Arc<WebSocketManager>) -> axum::Json<serde_json::Value> {
+    let claude_binary = ws_manager.cached_claude_binary().await;
+
+    axum::Json(serde_json::json!({
+        "status": "ok",
+        "claude_binary": claude_binary.map(|info| serde_json::json!({
+            "path": info.path,
+            "version": info.version,
+        })),
+    }))
 }
\ No newline at end of file
@@ -0,0 +1,84 @@
+use axum::{extract::Request, http::HeaderValue, middleware::Next, response::Response};
+use std::time::Instant;
+use tower_http::trace::MakeSpan;
+use tracing::Span;
+
+/// The request id attached to a request's extensions by
+/// [`request_id_middleware`], so downstream layers (and handlers, if they
+/// need it) can read back the same id that was put on the response header.
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// Stamp every request with an `x-request-id`, reusing one supplied by the
+/// caller or minting a UUID otherwise, and echo it back on the response so
+/// a client and the server logs can be correlated by the same id.
+pub async fn request_id_middleware(mut request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get("x-request-id")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    request.extensions_mut().insert(RequestId(request_id.clone()));
+
+    let mut response = next.run(request).await;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert("x-request-id", value);
+    }
+    response
+}
+
+/// Emit one structured `tracing` event per request with the fields a log
+/// aggregator needs to index a completed HTTP call - `method`, `path`,
+/// `status`, `latency_ms`, and `request_id` - rather than relying on
+/// [`TraceLayer`](tower_http::trace::TraceLayer)'s default on-response log,
+/// which doesn't carry all of those as top-level fields. Must run after
+/// [`request_id_middleware`] so the id is already in `request`'s extensions.
+pub async fn trace_completion_middleware(request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .map(|id| id.0.clone())
+        .unwrap_or_default();
+
+    let started_at = Instant::now();
+    let response = next.run(request).await;
+    let latency_ms = started_at.elapsed().as_millis();
+
+    tracing::info!(
+        method = %method,
+        path,
+        status = response.status().as_u16(),
+        latency_ms,
+        request_id,
+        "request completed"
+    );
+
+    response
+}
+
+/// Builds the span [`TraceLayer`](tower_http::trace::TraceLayer) opens for
+/// each request, carrying the id [`request_id_middleware`] assigned so every
+/// line logged while handling the request shares a `request_id` field.
+#[derive(Debug, Clone, Default)]
+pub struct RequestIdMakeSpan;
+
+impl<B> MakeSpan<B> for RequestIdMakeSpan {
+    fn make_span(&mut self, request: &axum::http::Request<B>) -> Span {
+        let request_id = request
+            .extensions()
+            .get::<RequestId>()
+            .map(|id| id.0.as_str())
+            .unwrap_or_default();
+
+        tracing::info_span!(
+            "http_request",
+            method = %request.method(),
+            uri = %request.uri(),
+            request_id,
+        )
+    }
+}
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// What a token is allowed to do. `ReadOnly` covers listing/inspection
+/// endpoints; `ServerManagement` additionally covers mutations such as
+/// adding an MCP server or starting a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    ReadOnly,
+    ServerManagement,
+}
+
+impl TokenScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenScope::ReadOnly => "read_only",
+            TokenScope::ServerManagement => "server_management",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "read_only" => Some(TokenScope::ReadOnly),
+            "server_management" => Some(TokenScope::ServerManagement),
+            _ => None,
+        }
+    }
+}
+
+/// Metadata about an issued API token. The raw token is only ever returned
+/// once, in [`IssueTokenResult`]; only its hash is persisted.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ApiToken {
+    pub id: i64,
+    pub name: String,
+    pub scope: TokenScope,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+    pub revoked: bool,
+}
+
+/// Request to mint a new API token.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct IssueTokenRequest {
+    pub name: String,
+    pub scope: TokenScope,
+}
+
+/// Result of minting a token.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct IssueTokenResult {
+    pub id: i64,
+    pub name: String,
+    pub scope: TokenScope,
+    pub token: String,
+}
+
+/// Request to exchange a static API key for a short-lived JWT.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct IssueJwtRequest {
+    pub api_key: String,
+}
+
+/// Result of a successful JWT exchange.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct IssueJwtResult {
+    pub token: String,
+    pub expires_in: u64,
+}
+
+/// The authenticated caller attached to request extensions by
+/// `auth::require_api_auth` once a bearer token has validated.
+#[derive(Debug, Clone)]
+pub struct AuthPrincipal {
+    pub token_id: i64,
+    pub name: String,
+    pub scope: TokenScope,
+}
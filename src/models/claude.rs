@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use utoipa::ToSchema;
 
 /// Represents a project in the ~/.claude/projects directory
@@ -43,6 +44,9 @@ pub struct StartSessionRequest {
     pub session_type: Option<String>,
     pub session_id: Option<String>,
     pub additional_args: Option<Vec<String>>,
+    /// Name of a host registered via `ClaudeService::register_remote_host`
+    /// to run this session against over SSH, instead of locally.
+    pub host_id: Option<String>,
 }
 
 /// Request to create a new project
@@ -68,6 +72,74 @@ pub struct ExecuteCommandRequest {
     pub command: String,
 }
 
+/// A single normalized event parsed from Claude's
+/// `--output-format stream-json` wire format, so callers consuming the
+/// WebSocket/SSE stream get a typed, discriminated protocol instead of
+/// having to re-parse Claude's raw line format themselves.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    /// Plain assistant-authored text.
+    AssistantText { text: String },
+    /// The assistant invoked a tool.
+    ToolUse { name: String, input: Value },
+    /// A tool call's result was returned to the assistant.
+    ToolResult { output: Value },
+    /// Token usage reported for a turn.
+    Usage { input_tokens: u64, output_tokens: u64 },
+    /// The session's initial system/init message.
+    SystemInit { session_id: Option<String>, model: Option<String> },
+    /// The final result summary Claude emits when a run completes.
+    Result { cost_usd: Option<f64>, duration_ms: Option<u64> },
+    /// A running token/cost tally derived from `Usage`/`Result` events so
+    /// clients can display spend live without tracking it themselves.
+    CostTally { input_tokens: u64, output_tokens: u64, cost_usd: f64 },
+    /// A line that didn't match any recognized shape, preserved as-is.
+    Raw { raw: Value },
+}
+
+/// Classify a single decoded `stream-json` line into a [`StreamEvent`].
+///
+/// Claude's wire format isn't formally documented, so this inspects the
+/// well-known `type`/`subtype` discriminants it currently emits and falls
+/// back to `Raw` for anything unrecognized rather than failing the stream.
+pub fn classify_stream_json(value: &Value) -> StreamEvent {
+    match value.get("type").and_then(Value::as_str) {
+        Some("assistant") => {
+            let text = value
+                .pointer("/message/content/0/text")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            StreamEvent::AssistantText { text }
+        }
+        Some("tool_use") => StreamEvent::ToolUse {
+            name: value.get("name").and_then(Value::as_str).unwrap_or_default().to_string(),
+            input: value.get("input").cloned().unwrap_or(Value::Null),
+        },
+        Some("tool_result") => StreamEvent::ToolResult {
+            output: value.get("output").cloned().unwrap_or(Value::Null),
+        },
+        Some("system") if value.get("subtype").and_then(Value::as_str) == Some("init") => {
+            StreamEvent::SystemInit {
+                session_id: value.get("session_id").and_then(Value::as_str).map(String::from),
+                model: value.get("model").and_then(Value::as_str).map(String::from),
+            }
+        }
+        Some("result") => StreamEvent::Result {
+            cost_usd: value.get("cost_usd").and_then(Value::as_f64),
+            duration_ms: value.get("duration_ms").and_then(Value::as_u64),
+        },
+        _ => match value.get("usage") {
+            Some(usage) => StreamEvent::Usage {
+                input_tokens: usage.get("input_tokens").and_then(Value::as_u64).unwrap_or(0),
+                output_tokens: usage.get("output_tokens").and_then(Value::as_u64).unwrap_or(0),
+            },
+            None => StreamEvent::Raw { raw: value.clone() },
+        },
+    }
+}
+
 /// Session record from database
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SessionRecord {
@@ -79,4 +151,15 @@ pub struct SessionRecord {
     pub created_at: String,
     pub status: String,
     pub output: Option<String>,
+    /// Id of the HTTP request that started this session, if it came in
+    /// through the API (rather than e.g. a test fixture inserting directly),
+    /// so a session can be traced back to the `POST /claude/sessions` call
+    /// that created it via the same id logged at the HTTP edge.
+    pub request_id: Option<String>,
+    /// Whether this session currently has a supervised `claude` process
+    /// running. Always `false` as read straight from the database; set by
+    /// `ClaudeService::list_sessions`/`get_session` by cross-referencing the
+    /// live session supervisor.
+    #[serde(default)]
+    pub running: bool,
 }
\ No newline at end of file
@@ -23,6 +23,10 @@ pub struct MCPServer {
     pub is_active: bool,
     /// Server status
     pub status: ServerStatus,
+    /// Absolute path of the project this server belongs to, for
+    /// `scope: "project"` servers stored in that project's `.mcp.json`.
+    /// Always `None` for `"user"`-scoped servers.
+    pub project_path: Option<String>,
 }
 
 /// Server status information
@@ -53,6 +57,9 @@ pub struct AddMCPServerRequest {
     pub url: Option<String>,
     /// Configuration scope: "local", "project", or "user"
     pub scope: Option<String>,
+    /// Path of the project to add this server to. Required when `scope` is
+    /// `"project"`; ignored otherwise.
+    pub project_path: Option<String>,
 }
 
 /// Request to update an MCP server
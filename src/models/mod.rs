@@ -1,11 +1,15 @@
 pub mod agent;
+pub mod auth;
 pub mod claude;
 pub mod mcp;
 
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-/// Storage usage statistics
+/// Storage usage statistics for the `~/.claude` tree, as returned by
+/// `handlers::storage::get_storage_usage`. `computed_at` is the Unix
+/// timestamp the snapshot was taken at - it lags behind "now" whenever the
+/// handler served this from its cache instead of recomputing.
 #[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct StorageUsage {
@@ -13,6 +17,35 @@ pub struct StorageUsage {
     pub total_files: usize,
     pub projects_count: usize,
     pub sessions_count: usize,
+    pub projects: Vec<ProjectStorageUsage>,
+    pub checkpoints: CategoryStorageUsage,
+    pub todos: CategoryStorageUsage,
+    pub ide: CategoryStorageUsage,
+    pub computed_at: u64,
+}
+
+/// Per-project breakdown of storage usage, one entry per directory under
+/// `~/.claude/projects`.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ProjectStorageUsage {
+    /// The project's directory name under `~/.claude/projects` (same
+    /// encoded form used as `Project::id` in `models::claude`).
+    pub path: String,
+    pub size_bytes: u64,
+    pub sessions_count: usize,
+    /// Unix timestamp of the most recently modified file in the project
+    /// directory, if it has any files at all.
+    pub most_recent_modified: Option<u64>,
+}
+
+/// File-count/size tally for a single non-project category (checkpoints,
+/// todos, IDE lock files) under `~/.claude`.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CategoryStorageUsage {
+    pub files: usize,
+    pub size_bytes: u64,
 }
 
 /// MCP Server configuration
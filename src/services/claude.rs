@@ -1,17 +1,46 @@
 use anyhow::Result;
 use base64::engine::Engine;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::process::Stdio;
 use std::sync::Arc;
-use tokio::process::Command;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{broadcast, oneshot, Mutex};
 use uuid::Uuid;
 
 use crate::models::claude::{Project, StartSessionRequest, SessionRecord};
+use crate::services::remote::{shell_quote, RemoteHost, RemoteTransport, SshTransport};
 use crate::services::DatabaseService;
+use crate::websocket::session_artifacts_dir;
+
+/// Capacity of the broadcast channel a supervised session's raw stdout
+/// lines are fanned out on; generous enough that a slow subscriber lags
+/// rather than a fast one's first lines getting dropped.
+const SESSION_OUTPUT_CHANNEL_CAPACITY: usize = 1000;
+
+/// A session's live handle inside [`ClaudeService::running_sessions`]: where
+/// to subscribe for its raw output, and how to ask its process to stop.
+struct RunningSession {
+    output_tx: broadcast::Sender<String>,
+    kill_tx: Option<oneshot::Sender<()>>,
+}
 
 pub struct ClaudeService {
     claude_binary_path: String,
     db_service: Arc<DatabaseService>,
+    transport: Arc<dyn RemoteTransport>,
+    /// SSH hosts registered via [`Self::register_remote_host`], keyed by the
+    /// name callers pass as `StartSessionRequest::host_id`.
+    remote_hosts: Mutex<HashMap<String, RemoteHost>>,
+    /// Path to `claude` on each remote host once uploaded/located, so it's
+    /// resolved once instead of re-probed on every session.
+    remote_binary_cache: Mutex<HashMap<String, String>>,
+    /// Locally-supervised `claude` processes started by [`Self::start_session`],
+    /// keyed by session id. Lets later calls check whether a session is
+    /// still running, subscribe to its live output, or kill it, instead of
+    /// the process handle being spawned and immediately dropped.
+    running_sessions: Arc<Mutex<HashMap<String, RunningSession>>>,
 }
 
 impl ClaudeService {
@@ -20,9 +49,164 @@ impl ClaudeService {
         Ok(ClaudeService {
             claude_binary_path: binary_path,
             db_service,
+            transport: Arc::new(SshTransport::new()),
+            remote_hosts: Mutex::new(HashMap::new()),
+            remote_binary_cache: Mutex::new(HashMap::new()),
+            running_sessions: Arc::new(Mutex::new(HashMap::new())),
         })
     }
 
+    /// Whether `session_id` currently has a locally supervised process running.
+    pub async fn is_running(&self, session_id: &str) -> bool {
+        self.running_sessions.lock().await.contains_key(session_id)
+    }
+
+    /// Subscribe to a running session's raw stdout lines, as they're read.
+    /// Returns `None` if the session isn't currently supervised (already
+    /// finished, or never started locally).
+    pub async fn subscribe_output(&self, session_id: &str) -> Option<broadcast::Receiver<String>> {
+        self.running_sessions
+            .lock()
+            .await
+            .get(session_id)
+            .map(|session| session.output_tx.subscribe())
+    }
+
+    /// Ask a running session's process to stop. Returns `false` if it isn't
+    /// currently running.
+    pub async fn cancel_session(&self, session_id: &str) -> bool {
+        if let Some(session) = self.running_sessions.lock().await.get_mut(session_id) {
+            if let Some(kill_tx) = session.kill_tx.take() {
+                return kill_tx.send(()).is_ok();
+            }
+        }
+        false
+    }
+
+    /// Take ownership of a just-spawned local `claude` process: register it
+    /// in `running_sessions`, then hand off to a background task that reads
+    /// its stdout line by line, appending each line to the session's
+    /// `events.jsonl` artifact and fanning it out to subscribers, until the
+    /// process exits or [`Self::cancel_session`] kills it. The final exit
+    /// status and accumulated output are persisted back onto the session's
+    /// `SessionRecord` via [`DatabaseService::update_session_status`].
+    async fn supervise(&self, session_id: String, mut child: Child) {
+        let Some(stdout) = child.stdout.take() else {
+            return;
+        };
+
+        let (output_tx, _) = broadcast::channel(SESSION_OUTPUT_CHANNEL_CAPACITY);
+        let (kill_tx, mut kill_rx) = oneshot::channel();
+
+        self.running_sessions.lock().await.insert(
+            session_id.clone(),
+            RunningSession {
+                output_tx: output_tx.clone(),
+                kill_tx: Some(kill_tx),
+            },
+        );
+
+        let running_sessions = self.running_sessions.clone();
+        let db_service = self.db_service.clone();
+        let events_path = session_artifacts_dir(&session_id).join("events.jsonl");
+
+        tokio::spawn(async move {
+            if let Some(parent) = events_path.parent() {
+                let _ = tokio::fs::create_dir_all(parent).await;
+            }
+            let mut events_log = tokio::fs::File::create(&events_path).await.ok();
+
+            let mut lines = BufReader::new(stdout).lines();
+            let mut output = String::new();
+
+            loop {
+                tokio::select! {
+                    biased;
+                    _ = &mut kill_rx => {
+                        let _ = child.start_kill();
+                        break;
+                    }
+                    line = lines.next_line() => {
+                        match line {
+                            Ok(Some(line)) => {
+                                output.push_str(&line);
+                                output.push('\n');
+                                if let Some(log) = events_log.as_mut() {
+                                    let _ = log.write_all(line.as_bytes()).await;
+                                    let _ = log.write_all(b"\n").await;
+                                }
+                                let _ = output_tx.send(line);
+                            }
+                            _ => break,
+                        }
+                    }
+                }
+            }
+
+            let success = child.wait().await.map(|status| status.success()).unwrap_or(false);
+            running_sessions.lock().await.remove(&session_id);
+
+            if let Err(e) = db_service.update_session_status(
+                &session_id,
+                if success { "completed" } else { "failed" },
+                Some(&output),
+            ).await {
+                tracing::error!(
+                    "Failed to persist final status for session {}: {}",
+                    session_id,
+                    e
+                );
+            }
+        });
+    }
+
+    /// Register a named SSH host so sessions can be started against it by
+    /// passing `host_id` in [`StartSessionRequest`] instead of running
+    /// locally.
+    pub async fn register_remote_host(&self, name: String, host: RemoteHost) {
+        self.remote_hosts.lock().await.insert(name, host);
+    }
+
+    async fn remote_host(&self, name: &str) -> Option<RemoteHost> {
+        self.remote_hosts.lock().await.get(name).cloned()
+    }
+
+    /// Locate (and cache) the `claude` binary on `host`, uploading this
+    /// server's own binary if the host doesn't have a working one.
+    async fn resolve_remote_binary(&self, host: &RemoteHost) -> Result<String> {
+        let cache_key = format!("{}@{}", host.user, host.host);
+        if let Some(cached) = self.remote_binary_cache.lock().await.get(&cache_key) {
+            return Ok(cached.clone());
+        }
+
+        let remote_bin_dir = host.remote_home.join(".local").join("bin");
+        let remote_path = remote_bin_dir.join("claude").to_string_lossy().into_owned();
+
+        let probe = self
+            .transport
+            .run_command(host, &format!("{} --version", shell_quote(&remote_path)))
+            .await;
+        let needs_upload = !matches!(probe, Ok(output) if output.success);
+
+        if needs_upload {
+            self.transport
+                .run_command(host, &format!("mkdir -p {}", shell_quote(&remote_bin_dir.to_string_lossy())))
+                .await?;
+            self.transport
+                .upload_file(host, std::path::Path::new(&self.claude_binary_path), &remote_path)
+                .await?;
+            self.transport
+                .run_command(host, &format!("chmod +x {}", shell_quote(&remote_path)))
+                .await?;
+        }
+
+        self.remote_binary_cache
+            .lock()
+            .await
+            .insert(cache_key, remote_path.clone());
+        Ok(remote_path)
+    }
+
     fn find_claude_binary() -> Result<String> {
         // Try to find claude binary in PATH
         if let Ok(path) = which::which("claude") {
@@ -55,6 +239,55 @@ impl ClaudeService {
         Err(anyhow::anyhow!("Claude binary not found. Please install Claude Code CLI."))
     }
 
+    /// List projects on `host_id`'s `~/.claude/projects` if given, otherwise
+    /// the local one.
+    pub async fn get_projects_for_host(&self, host_id: Option<&str>) -> Result<Vec<Project>> {
+        let host = match host_id {
+            Some(id) => self.remote_host(id).await,
+            None => None,
+        };
+        let Some(host) = host else {
+            return self.get_projects();
+        };
+
+        let projects_dir = host.remote_home.join(".claude").join("projects");
+        let entries = self
+            .transport
+            .read_dir(&host, &projects_dir.to_string_lossy())
+            .await?;
+
+        let mut projects = Vec::new();
+        for entry in entries.into_iter().filter(|entry| entry.is_dir) {
+            let decoded_path = base64::engine::general_purpose::STANDARD
+                .decode(&entry.name)
+                .and_then(|bytes| String::from_utf8(bytes).map_err(|_| base64::DecodeError::InvalidByte(0, 0)))
+                .unwrap_or_else(|_| entry.name.clone());
+
+            let session_dir = projects_dir.join(&entry.name);
+            let session_entries = self
+                .transport
+                .read_dir(&host, &session_dir.to_string_lossy())
+                .await
+                .unwrap_or_default();
+
+            let sessions = session_entries
+                .into_iter()
+                .filter(|entry| !entry.is_dir && entry.name.ends_with(".jsonl"))
+                .map(|entry| entry.name.trim_end_matches(".jsonl").to_string())
+                .collect();
+
+            projects.push(Project {
+                id: entry.name.clone(),
+                path: decoded_path,
+                sessions,
+                created_at: 0,
+                most_recent_session: None,
+            });
+        }
+
+        Ok(projects)
+    }
+
     pub fn get_projects(&self) -> Result<Vec<Project>> {
         let claude_dir = dirs::home_dir()
             .ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?
@@ -139,9 +372,17 @@ impl ClaudeService {
         Ok(projects)
     }
 
-    pub async fn start_session(&self, request: StartSessionRequest) -> Result<String> {
+    pub async fn start_session(&self, request: StartSessionRequest, request_id: Option<String>) -> Result<String> {
+        if let Some(host_id) = request.host_id.clone() {
+            if let Some(host) = self.remote_host(&host_id).await {
+                return self.start_remote_session(request, &host, request_id).await;
+            }
+            anyhow::bail!("Unknown remote host: {host_id}");
+        }
+
         let session_id = Uuid::new_v4().to_string();
-        
+        let model = request.model.clone().unwrap_or_else(|| "claude-3-5-sonnet-20241022".to_string());
+
         let mut cmd = Command::new(&self.claude_binary_path);
         cmd.arg("--project-path")
            .arg(&request.project_path);
@@ -154,23 +395,82 @@ impl ClaudeService {
             cmd.args(args);
         }
 
-        // Start Claude process but don't wait for it to complete
-        let _child = cmd
+        let child = cmd
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()?;
 
-        // In a real implementation, you would manage the process lifecycle
-        // For now, we just return the session ID
+        self.db_service.create_session_record(
+            &session_id,
+            &request.prompt,
+            &request.project_path,
+            &model,
+            request_id.as_deref(),
+        ).await?;
+        self.supervise(session_id.clone(), child).await;
+
+        Ok(session_id)
+    }
+
+    /// Run `start_session` against `host` over SSH: resolve/upload the
+    /// remote `claude` binary, build the equivalent command line, and spawn
+    /// it remotely, piping stdout/stderr back the same way a local spawn
+    /// does.
+    async fn start_remote_session(
+        &self,
+        request: StartSessionRequest,
+        host: &RemoteHost,
+        request_id: Option<String>,
+    ) -> Result<String> {
+        let session_id = Uuid::new_v4().to_string();
+        let binary_path = self.resolve_remote_binary(host).await?;
+
+        let mut command = format!(
+            "{} --project-path {}",
+            shell_quote(&binary_path),
+            shell_quote(&request.project_path)
+        );
+
+        if let Some(model) = &request.model {
+            command.push_str(&format!(" --model {}", shell_quote(model)));
+        }
+
+        if let Some(args) = &request.additional_args {
+            for arg in args {
+                command.push(' ');
+                command.push_str(&shell_quote(arg));
+            }
+        }
+
+        let model = request.model.clone().unwrap_or_else(|| "claude-3-5-sonnet-20241022".to_string());
+        let child = self.transport.spawn_streaming(host, &command).await?;
+
+        self.db_service.create_session_record(
+            &session_id,
+            &request.prompt,
+            &request.project_path,
+            &model,
+            request_id.as_deref(),
+        ).await?;
+        self.supervise(session_id.clone(), child).await;
+
         Ok(session_id)
     }
 
     // Session management methods
     pub async fn list_sessions(&self, project_path: Option<&str>) -> Result<Vec<SessionRecord>> {
-        self.db_service.get_sessions(project_path)
+        let mut sessions = self.db_service.get_sessions(project_path).await?;
+        for session in &mut sessions {
+            session.running = self.is_running(&session.session_id).await;
+        }
+        Ok(sessions)
     }
 
     pub async fn get_session(&self, session_id: &str) -> Result<Option<SessionRecord>> {
-        self.db_service.get_session(session_id)
+        let mut session = self.db_service.get_session(session_id).await?;
+        if let Some(session) = session.as_mut() {
+            session.running = self.is_running(&session.session_id).await;
+        }
+        Ok(session)
     }
 }
\ No newline at end of file
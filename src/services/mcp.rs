@@ -2,15 +2,24 @@ use crate::config::ClaudeConfig;
 use crate::error::{AppError, Result};
 use crate::models::mcp::{
     AddMCPServerRequest, ConnectionTestResult, ImportResult, ImportServerResult,
-    MCPServer, MCPServerResult, UpdateMCPServerRequest,
+    MCPServer, MCPServerResult, ServerStatus, UpdateMCPServerRequest,
 };
+use crate::services::DatabaseService;
 use anyhow::Context;
-use serde_json::Value;
+use serde_json::{Map, Value};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
-use std::time::SystemTime;
-use tokio::process::Command as AsyncCommand;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{ChildStdin, Command as AsyncCommand};
+
+/// How long a `test_connection` handshake may take before the server is
+/// considered unreachable, killing the child (stdio) or the in-flight
+/// request (SSE) rather than hanging forever.
+const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
 
 #[derive(Debug, Clone)]
 pub struct MCPService {
@@ -18,16 +27,49 @@ pub struct MCPService {
     claude_config: ClaudeConfig,
     #[allow(dead_code)]
     claude_binary_path: Option<PathBuf>,
+    handshake_timeout: Duration,
+    /// Used to enumerate known projects so project-scoped servers stored in
+    /// each project's `.mcp.json` can be found without a project path being
+    /// passed on every read.
+    db: Arc<DatabaseService>,
+    /// One lock per config file path, so two concurrent CRUD calls against
+    /// the *same* `claude.json`/`.mcp.json` serialize their
+    /// read-modify-write instead of racing and silently dropping one
+    /// update, while calls against different files (e.g. two different
+    /// projects) don't block each other.
+    config_locks: Arc<Mutex<HashMap<PathBuf, Arc<tokio::sync::Mutex<()>>>>>,
 }
 
 impl MCPService {
-    pub fn new() -> Result<Self> {
+    pub fn new(db: Arc<DatabaseService>) -> Result<Self> {
         Ok(Self {
             claude_config: Default::default(), // 临时使用默认值
             claude_binary_path: None,
+            handshake_timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+            db,
+            config_locks: Arc::new(Mutex::new(HashMap::new())),
         })
     }
 
+    /// Get (creating if absent) the lock guarding `path`'s
+    /// read-modify-write section. Held for the duration of a single CRUD
+    /// call, not across the whole service, so unrelated config files stay
+    /// independent.
+    fn config_lock(&self, path: &Path) -> Arc<tokio::sync::Mutex<()>> {
+        self.config_locks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(path.to_path_buf())
+            .or_insert_with(|| Arc::new(tokio::sync::Mutex::new(())))
+            .clone()
+    }
+
+    /// Override the handshake timeout (the default is 10 seconds).
+    pub fn with_handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = timeout;
+        self
+    }
+
     /// List all MCP servers from all scopes
     pub async fn list_servers(&self) -> Result<Vec<MCPServer>> {
         let mut servers = Vec::new();
@@ -78,12 +120,13 @@ impl MCPService {
         name: &str,
         request: UpdateMCPServerRequest,
     ) -> Result<MCPServerResult> {
-        // First find the server to determine its scope
+        // First find the server to determine its scope (and, for project
+        // scope, which project's .mcp.json it lives in).
         let server = self.get_server(name).await?;
-        
+
         match server.scope.as_str() {
-            "user" => self.update_user_server(name, request).await,
-            "project" => self.update_project_server(name, request).await,
+            "user" => self.update_user_server(&server, request).await,
+            "project" => self.update_project_server(&server, request).await,
             _ => Err(AppError::McpError(format!(
                 "Unknown scope: {}",
                 server.scope
@@ -94,10 +137,10 @@ impl MCPService {
     /// Remove an MCP server
     pub async fn remove_server(&self, name: &str) -> Result<MCPServerResult> {
         let server = self.get_server(name).await?;
-        
+
         match server.scope.as_str() {
-            "user" => self.remove_user_server(name).await,
-            "project" => self.remove_project_server(name).await,
+            "user" => self.remove_user_server(&server).await,
+            "project" => self.remove_project_server(&server).await,
             _ => Err(AppError::McpError(format!(
                 "Unknown scope: {}",
                 server.scope
@@ -178,19 +221,53 @@ impl MCPService {
     // Private helper methods
 
     async fn get_user_servers(&self) -> Result<Vec<MCPServer>> {
-        // Implementation to read from Claude Desktop config
-        // This would parse the Claude Desktop configuration file
-        // and convert to our MCPServer format
-        Ok(vec![]) // Placeholder
+        let path = self.get_claude_desktop_config_path()?;
+        let mcp_servers = Self::read_mcp_servers_object(&path)?;
+
+        Ok(mcp_servers
+            .into_iter()
+            .map(|(name, config)| mcp_server_from_config(name, &config, "user", None))
+            .collect())
     }
 
     async fn get_project_servers(&self) -> Result<Vec<MCPServer>> {
-        // Implementation to read from .mcp.json files in projects
-        Ok(vec![]) // Placeholder
+        let mut servers = Vec::new();
+
+        for project in self.db.get_projects().await? {
+            let config_path = Path::new(&project.path).join(".mcp.json");
+            if !config_path.exists() {
+                continue;
+            }
+
+            let mcp_servers = Self::read_mcp_servers_object(&config_path)?;
+            servers.extend(
+                mcp_servers
+                    .into_iter()
+                    .map(|(name, config)| {
+                        mcp_server_from_config(name, &config, "project", Some(project.path.clone()))
+                    }),
+            );
+        }
+
+        Ok(servers)
     }
 
     async fn add_user_server(&self, request: AddMCPServerRequest) -> Result<MCPServerResult> {
-        // Implementation to add server to Claude Desktop config
+        let path = self.get_claude_desktop_config_path()?;
+        let _guard = self.config_lock(&path).lock().await;
+        let mut root = Self::read_config_root(&path)?;
+        let mcp_servers = Self::mcp_servers_object_mut(&mut root);
+
+        if mcp_servers.contains_key(&request.name) {
+            return Err(AppError::Conflict {
+                resource: "MCP Server".to_string(),
+                message: format!("a server named '{}' already exists", request.name),
+            });
+        }
+
+        mcp_servers.insert(request.name.clone(), server_config_from_add_request(&request));
+        Self::write_config_root(&path, &root)?;
+
         Ok(MCPServerResult {
             success: true,
             message: "Server added successfully".to_string(),
@@ -199,7 +276,32 @@ impl MCPService {
     }
 
     async fn add_project_server(&self, request: AddMCPServerRequest) -> Result<MCPServerResult> {
-        // Implementation to add server to project .mcp.json
+        let project_path = request.project_path.clone().ok_or_else(|| AppError::InvalidInput {
+            field: "project_path".to_string(),
+            message: "project_path is required when scope is 'project'".to_string(),
+        })?;
+        if request.command.is_none() {
+            return Err(AppError::InvalidInput {
+                field: "command".to_string(),
+                message: "project-scoped servers must specify a command".to_string(),
+            });
+        }
+
+        let config_path = Path::new(&project_path).join(".mcp.json");
+        let _guard = self.config_lock(&config_path).lock().await;
+        let mut root = Self::read_config_root(&config_path)?;
+        let mcp_servers = Self::mcp_servers_object_mut(&mut root);
+
+        if mcp_servers.contains_key(&request.name) {
+            return Err(AppError::Conflict {
+                resource: "MCP Server".to_string(),
+                message: format!("a server named '{}' already exists in this project", request.name),
+            });
+        }
+
+        mcp_servers.insert(request.name.clone(), server_config_from_add_request(&request));
+        Self::write_config_root(&config_path, &root)?;
+
         Ok(MCPServerResult {
             success: true,
             message: "Server added successfully".to_string(),
@@ -209,41 +311,79 @@ impl MCPService {
 
     async fn update_user_server(
         &self,
-        name: &str,
-        _request: UpdateMCPServerRequest,
+        server: &MCPServer,
+        request: UpdateMCPServerRequest,
     ) -> Result<MCPServerResult> {
-        // Implementation to update user server
-        Ok(MCPServerResult {
-            success: true,
-            message: "Server updated successfully".to_string(),
-            server_name: Some(name.to_string()),
-        })
+        let path = self.get_claude_desktop_config_path()?;
+        self.update_server_in_file(&path, server, request).await
     }
 
     async fn update_project_server(
         &self,
-        name: &str,
-        _request: UpdateMCPServerRequest,
+        server: &MCPServer,
+        request: UpdateMCPServerRequest,
     ) -> Result<MCPServerResult> {
-        // Implementation to update project server
+        let project_path = server.project_path.as_ref().ok_or_else(|| {
+            AppError::InternalError(format!(
+                "project-scoped server '{}' is missing its project path",
+                server.name
+            ))
+        })?;
+        let config_path = Path::new(project_path).join(".mcp.json");
+        self.update_server_in_file(&config_path, server, request).await
+    }
+
+    async fn update_server_in_file(
+        &self,
+        path: &Path,
+        server: &MCPServer,
+        request: UpdateMCPServerRequest,
+    ) -> Result<MCPServerResult> {
+        let _guard = self.config_lock(path).lock().await;
+        let mut root = Self::read_config_root(path)?;
+        let mcp_servers = Self::mcp_servers_object_mut(&mut root);
+
+        let existing = mcp_servers.get(&server.name).cloned().unwrap_or(Value::Null);
+        mcp_servers.insert(server.name.clone(), merge_server_config(&existing, &request));
+        Self::write_config_root(path, &root)?;
+
         Ok(MCPServerResult {
             success: true,
             message: "Server updated successfully".to_string(),
-            server_name: Some(name.to_string()),
+            server_name: Some(server.name.clone()),
         })
     }
 
-    async fn remove_user_server(&self, name: &str) -> Result<MCPServerResult> {
-        // Implementation to remove user server
-        Ok(MCPServerResult {
-            success: true,
-            message: "Server removed successfully".to_string(),
-            server_name: Some(name.to_string()),
-        })
+    async fn remove_user_server(&self, server: &MCPServer) -> Result<MCPServerResult> {
+        let path = self.get_claude_desktop_config_path()?;
+        self.remove_server_from_file(&path, &server.name).await
+    }
+
+    async fn remove_project_server(&self, server: &MCPServer) -> Result<MCPServerResult> {
+        let project_path = server.project_path.as_ref().ok_or_else(|| {
+            AppError::InternalError(format!(
+                "project-scoped server '{}' is missing its project path",
+                server.name
+            ))
+        })?;
+        let config_path = Path::new(project_path).join(".mcp.json");
+        self.remove_server_from_file(&config_path, &server.name).await
     }
 
-    async fn remove_project_server(&self, name: &str) -> Result<MCPServerResult> {
-        // Implementation to remove project server
+    async fn remove_server_from_file(&self, path: &Path, name: &str) -> Result<MCPServerResult> {
+        let _guard = self.config_lock(path).lock().await;
+        let mut root = Self::read_config_root(path)?;
+        let mcp_servers = Self::mcp_servers_object_mut(&mut root);
+
+        if mcp_servers.remove(name).is_none() {
+            return Err(AppError::NotFound {
+                resource: "MCP Server".to_string(),
+                id: name.to_string(),
+            });
+        }
+
+        Self::write_config_root(path, &root)?;
+
         Ok(MCPServerResult {
             success: true,
             message: "Server removed successfully".to_string(),
@@ -251,102 +391,266 @@ impl MCPService {
         })
     }
 
+    /// Read a config file's root JSON object, returning an empty object if
+    /// the file doesn't exist yet (e.g. a project with no `.mcp.json`).
+    fn read_config_root(path: &Path) -> Result<Value> {
+        if !path.exists() {
+            return Ok(Value::Object(Map::new()));
+        }
+
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("failed to parse {} as JSON", path.display()))
+            .map_err(Into::into)
+    }
+
+    /// Read just the `mcpServers` object out of a config file, ignoring any
+    /// unrelated top-level keys it may hold.
+    fn read_mcp_servers_object(path: &Path) -> Result<Map<String, Value>> {
+        if !path.exists() {
+            return Ok(Map::new());
+        }
+
+        let root = Self::read_config_root(path)?;
+        Ok(root
+            .get("mcpServers")
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    /// Get (creating if absent) the `mcpServers` object within `root`, so
+    /// callers can mutate it in place while preserving every other
+    /// top-level key already in the file.
+    fn mcp_servers_object_mut(root: &mut Value) -> &mut Map<String, Value> {
+        if !matches!(root, Value::Object(_)) {
+            *root = Value::Object(Map::new());
+        }
+        let root_obj = root.as_object_mut().expect("just coerced to an object");
+
+        if !matches!(root_obj.get("mcpServers"), Some(Value::Object(_))) {
+            root_obj.insert("mcpServers".to_string(), Value::Object(Map::new()));
+        }
+        root_obj
+            .get_mut("mcpServers")
+            .and_then(Value::as_object_mut)
+            .expect("just ensured mcpServers is an object")
+    }
+
+    /// Serialize `root` and atomically replace the file at `path`: write to
+    /// a sibling temp file, then rename it into place, so a crash or
+    /// concurrent write mid-save can never leave a half-written config.
+    fn write_config_root(path: &Path, root: &Value) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+
+        let serialized = serde_json::to_string_pretty(root)?;
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serialized)
+            .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, path)
+            .with_context(|| format!("failed to replace {}", path.display()))?;
+
+        Ok(())
+    }
+
+    /// Test a stdio server by actually speaking MCP to it: spawn the
+    /// command, send `initialize`, then `notifications/initialized` and
+    /// `tools/list`, so a "successful" result means the server implements
+    /// the protocol rather than just that the process exited cleanly.
     async fn test_stdio_connection(&self, server: &MCPServer) -> Result<ConnectionTestResult> {
+        let Some(command) = &server.command else {
+            return Ok(ConnectionTestResult {
+                success: false,
+                message: "No command specified for stdio transport".to_string(),
+                response_time_ms: None,
+                details: None,
+            });
+        };
+
         let start_time = SystemTime::now();
-        
-        if let Some(command) = &server.command {
-            match AsyncCommand::new(command)
-                .args(&server.args)
-                .envs(&server.env)
-                .output()
-                .await
-            {
-                Ok(output) => {
-                    let response_time = start_time.elapsed().unwrap().as_millis() as u64;
-                    
-                    Ok(ConnectionTestResult {
-                        success: output.status.success(),
-                        message: if output.status.success() {
-                            "Connection successful".to_string()
-                        } else {
-                            format!("Connection failed: {}", String::from_utf8_lossy(&output.stderr))
-                        },
-                        response_time_ms: Some(response_time),
-                        details: Some(serde_json::json!({
-                            "stdout": String::from_utf8_lossy(&output.stdout),
-                            "stderr": String::from_utf8_lossy(&output.stderr),
-                            "status_code": output.status.code()
-                        })),
-                    })
-                }
-                Err(e) => Ok(ConnectionTestResult {
+
+        let mut child = match AsyncCommand::new(command)
+            .args(&server.args)
+            .envs(&server.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => {
+                return Ok(ConnectionTestResult {
                     success: false,
-                    message: format!("Failed to execute command: {}", e),
+                    message: format!("Failed to spawn command: {}", e),
                     response_time_ms: None,
                     details: None,
-                }),
+                })
             }
-        } else {
-            Ok(ConnectionTestResult {
+        };
+
+        let handshake = tokio::time::timeout(self.handshake_timeout, Self::run_stdio_handshake(&mut child)).await;
+
+        // The handshake only needs the process alive long enough to answer
+        // two requests; don't leave it running regardless of the outcome.
+        let _ = child.start_kill();
+        let _ = child.wait().await;
+
+        let response_time = start_time.elapsed().unwrap_or_default().as_millis() as u64;
+
+        Ok(match handshake {
+            Ok(Ok((protocol_version, server_info, tools))) => ConnectionTestResult {
+                success: true,
+                message: "MCP handshake succeeded".to_string(),
+                response_time_ms: Some(response_time),
+                details: Some(serde_json::json!({
+                    "protocolVersion": protocol_version,
+                    "serverInfo": server_info,
+                    "tools": tools,
+                })),
+            },
+            Ok(Err(e)) => ConnectionTestResult {
                 success: false,
-                message: "No command specified for stdio transport".to_string(),
-                response_time_ms: None,
+                message: format!("MCP handshake failed: {}", e),
+                response_time_ms: Some(response_time),
                 details: None,
+            },
+            Err(_) => ConnectionTestResult {
+                success: false,
+                message: format!("MCP handshake timed out after {:?}", self.handshake_timeout),
+                response_time_ms: Some(response_time),
+                details: None,
+            },
+        })
+    }
+
+    /// Speak the `initialize` / `notifications/initialized` / `tools/list`
+    /// sequence over `child`'s stdio, returning the negotiated protocol
+    /// version, server info, and discovered tool names.
+    async fn run_stdio_handshake(child: &mut tokio::process::Child) -> Result<(String, Value, Vec<String>)> {
+        let mut stdin = child.stdin.take().context("child has no stdin")?;
+        let stdout = child.stdout.take().context("child has no stdout")?;
+        let mut lines = BufReader::new(stdout).lines();
+
+        write_jsonrpc_message(&mut stdin, &initialize_request()).await?;
+        let response = lines
+            .next_line()
+            .await?
+            .context("server closed stdout before responding to initialize")?;
+        let response: Value = serde_json::from_str(&response).context("malformed initialize response")?;
+
+        let result = response.get("result").context("initialize response missing `result`")?;
+        let protocol_version = result
+            .get("protocolVersion")
+            .and_then(Value::as_str)
+            .context("initialize result missing protocolVersion")?
+            .to_string();
+        let server_info = result.get("serverInfo").cloned().unwrap_or(Value::Null);
+
+        write_jsonrpc_message(&mut stdin, &initialized_notification()).await?;
+        write_jsonrpc_message(&mut stdin, &tools_list_request()).await?;
+
+        let tools_response = lines
+            .next_line()
+            .await?
+            .context("server closed stdout before responding to tools/list")?;
+        let tools_response: Value =
+            serde_json::from_str(&tools_response).context("malformed tools/list response")?;
+        let tools = tools_response
+            .pointer("/result/tools")
+            .and_then(Value::as_array)
+            .map(|tools| {
+                tools
+                    .iter()
+                    .filter_map(|tool| tool.get("name").and_then(Value::as_str).map(String::from))
+                    .collect()
             })
-        }
+            .unwrap_or_default();
+
+        Ok((protocol_version, server_info, tools))
     }
 
+    /// Test an SSE/streamable-HTTP server by POSTing the same `initialize`
+    /// body a stdio server would get over stdin.
     async fn test_sse_connection(&self, server: &MCPServer) -> Result<ConnectionTestResult> {
-        if let Some(url) = &server.url {
-            let start_time = SystemTime::now();
-            
-            match reqwest::get(url).await {
-                Ok(response) => {
-                    let response_time = start_time.elapsed().unwrap().as_millis() as u64;
-                    
-                    Ok(ConnectionTestResult {
-                        success: response.status().is_success(),
-                        message: format!("HTTP {}", response.status()),
-                        response_time_ms: Some(response_time),
-                        details: Some(serde_json::json!({
-                            "status_code": response.status().as_u16(),
-                            "headers": response.headers().iter()
-                                .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
-                                .collect::<HashMap<String, String>>()
-                        })),
-                    })
-                }
-                Err(e) => Ok(ConnectionTestResult {
-                    success: false,
-                    message: format!("Connection failed: {}", e),
-                    response_time_ms: None,
-                    details: None,
-                }),
-            }
-        } else {
-            Ok(ConnectionTestResult {
+        let Some(url) = &server.url else {
+            return Ok(ConnectionTestResult {
                 success: false,
                 message: "No URL specified for SSE transport".to_string(),
                 response_time_ms: None,
                 details: None,
-            })
-        }
-    }
+            });
+        };
 
-    async fn import_server_from_config(
-        &self,
-        name: &str,
-        _config: &Value,
-    ) -> Result<MCPServerResult> {
-        // Parse the server configuration from Claude Desktop format
-        // and add it as a user-level server
-        Ok(MCPServerResult {
-            success: true,
-            message: format!("Imported server: {}", name),
-            server_name: Some(name.to_string()),
+        let start_time = SystemTime::now();
+        let client = reqwest::Client::new();
+
+        let handshake = tokio::time::timeout(self.handshake_timeout, async {
+            let response = client
+                .post(url)
+                .header("Accept", "application/json, text/event-stream")
+                .json(&initialize_request())
+                .send()
+                .await
+                .context("failed to POST initialize")?;
+
+            let status = response.status();
+            let body = response.text().await.context("failed to read response body")?;
+            let initialize_response = parse_mcp_http_response(&body).context("malformed initialize response")?;
+
+            let result = initialize_response
+                .get("result")
+                .context("initialize response missing `result`")?;
+            let protocol_version = result
+                .get("protocolVersion")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            let server_info = result.get("serverInfo").cloned().unwrap_or(Value::Null);
+
+            anyhow::Ok((status, protocol_version, server_info))
+        })
+        .await;
+
+        let response_time = start_time.elapsed().unwrap_or_default().as_millis() as u64;
+
+        Ok(match handshake {
+            Ok(Ok((status, protocol_version, server_info))) => ConnectionTestResult {
+                success: status.is_success(),
+                message: format!("HTTP {}", status),
+                response_time_ms: Some(response_time),
+                details: Some(serde_json::json!({
+                    "protocolVersion": protocol_version,
+                    "serverInfo": server_info,
+                    "status_code": status.as_u16(),
+                })),
+            },
+            Ok(Err(e)) => ConnectionTestResult {
+                success: false,
+                message: format!("MCP handshake failed: {}", e),
+                response_time_ms: Some(response_time),
+                details: None,
+            },
+            Err(_) => ConnectionTestResult {
+                success: false,
+                message: format!("MCP handshake timed out after {:?}", self.handshake_timeout),
+                response_time_ms: Some(response_time),
+                details: None,
+            },
         })
     }
 
+    /// Import a single `mcpServers` entry from a Claude Desktop config,
+    /// inferring its transport (`command` ⇒ stdio, `url` ⇒ sse) and
+    /// persisting it as a user-scoped server.
+    async fn import_server_from_config(&self, name: &str, config: &Value) -> Result<MCPServerResult> {
+        let request = add_request_from_server_config(name, config)?;
+        self.add_user_server(request).await
+    }
+
     fn get_claude_desktop_config_path(&self) -> Result<PathBuf> {
         let home_dir = dirs::home_dir().ok_or_else(|| {
             AppError::InternalError("Could not determine home directory".to_string())
@@ -374,4 +678,200 @@ impl MCPService {
 
         Ok(config_path)
     }
+}
+
+/// The JSON-RPC 2.0 `initialize` request every MCP handshake starts with.
+fn initialize_request() -> Value {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": {
+                "name": "opcode",
+                "version": env!("CARGO_PKG_VERSION"),
+            }
+        }
+    })
+}
+
+/// The notification a client sends once it has accepted the server's
+/// `initialize` response, per the MCP lifecycle.
+fn initialized_notification() -> Value {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/initialized",
+    })
+}
+
+/// Follow-up request used to confirm the server actually exposes tools,
+/// rather than just completing the handshake.
+fn tools_list_request() -> Value {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "tools/list",
+        "params": {}
+    })
+}
+
+/// Write a single newline-delimited JSON-RPC message to a child's stdin.
+async fn write_jsonrpc_message(stdin: &mut ChildStdin, message: &Value) -> Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    stdin.write_all(line.as_bytes()).await?;
+    stdin.flush().await?;
+    Ok(())
+}
+
+/// Parse an MCP HTTP response body as either a plain JSON-RPC reply or a
+/// single `data: ...` Server-Sent Events frame carrying one.
+fn parse_mcp_http_response(body: &str) -> Result<Value> {
+    if let Ok(value) = serde_json::from_str::<Value>(body) {
+        return Ok(value);
+    }
+
+    for line in body.lines() {
+        if let Some(data) = line.strip_prefix("data:") {
+            return serde_json::from_str(data.trim()).context("malformed event-stream payload");
+        }
+    }
+
+    anyhow::bail!("response was neither JSON nor a recognizable event-stream payload")
+}
+
+/// Build the on-disk JSON entry for a server being added: stdio servers
+/// store `command`/`args`/`env`, sse servers store `url`.
+fn server_config_from_add_request(request: &AddMCPServerRequest) -> Value {
+    let mut entry = Map::new();
+
+    match request.transport.as_str() {
+        "sse" => {
+            if let Some(url) = &request.url {
+                entry.insert("url".to_string(), Value::String(url.clone()));
+            }
+        }
+        _ => {
+            if let Some(command) = &request.command {
+                entry.insert("command".to_string(), Value::String(command.clone()));
+            }
+            entry.insert(
+                "args".to_string(),
+                serde_json::to_value(request.args.clone().unwrap_or_default()).unwrap_or(Value::Array(vec![])),
+            );
+            entry.insert(
+                "env".to_string(),
+                serde_json::to_value(request.env.clone().unwrap_or_default()).unwrap_or(Value::Object(Map::new())),
+            );
+        }
+    }
+
+    entry.insert("isActive".to_string(), Value::Bool(true));
+
+    Value::Object(entry)
+}
+
+/// Apply an `UpdateMCPServerRequest`'s `Some` fields on top of an existing
+/// on-disk entry, leaving every field the request didn't mention untouched.
+fn merge_server_config(existing: &Value, request: &UpdateMCPServerRequest) -> Value {
+    let mut entry = existing.as_object().cloned().unwrap_or_default();
+
+    if let Some(command) = &request.command {
+        entry.insert("command".to_string(), Value::String(command.clone()));
+    }
+    if let Some(args) = &request.args {
+        entry.insert("args".to_string(), serde_json::to_value(args).unwrap_or(Value::Array(vec![])));
+    }
+    if let Some(env) = &request.env {
+        entry.insert("env".to_string(), serde_json::to_value(env).unwrap_or(Value::Object(Map::new())));
+    }
+    if let Some(url) = &request.url {
+        entry.insert("url".to_string(), Value::String(url.clone()));
+    }
+    if let Some(is_active) = request.is_active {
+        entry.insert("isActive".to_string(), Value::Bool(is_active));
+    }
+
+    Value::Object(entry)
+}
+
+/// Convert one `mcpServers` entry read from disk back into our domain
+/// model, inferring `transport` the same way import does: a `command`
+/// means stdio, a bare `url` means sse.
+fn mcp_server_from_config(name: String, config: &Value, scope: &str, project_path: Option<String>) -> MCPServer {
+    let command = config.get("command").and_then(Value::as_str).map(String::from);
+    let url = config.get("url").and_then(Value::as_str).map(String::from);
+    let transport = if command.is_some() { "stdio" } else { "sse" }.to_string();
+    let args = config
+        .get("args")
+        .and_then(Value::as_array)
+        .map(|a| a.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+        .unwrap_or_default();
+    let env = config
+        .get("env")
+        .and_then(Value::as_object)
+        .map(|m| {
+            m.iter()
+                .filter_map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+    let is_active = config.get("isActive").and_then(Value::as_bool).unwrap_or(true);
+
+    MCPServer {
+        name,
+        transport,
+        command,
+        args,
+        env,
+        url,
+        scope: scope.to_string(),
+        is_active,
+        status: ServerStatus {
+            running: false,
+            error: None,
+            last_checked: None,
+        },
+        project_path,
+    }
+}
+
+/// Build an [`AddMCPServerRequest`] for a Claude Desktop `mcpServers` entry
+/// being imported, inferring its transport from which fields are present.
+fn add_request_from_server_config(name: &str, config: &Value) -> Result<AddMCPServerRequest> {
+    let command = config.get("command").and_then(Value::as_str).map(String::from);
+    let url = config.get("url").and_then(Value::as_str).map(String::from);
+
+    let transport = if command.is_some() {
+        "stdio"
+    } else if url.is_some() {
+        "sse"
+    } else {
+        return Err(AppError::InvalidInput {
+            field: "command/url".to_string(),
+            message: format!("server '{}' has neither a command nor a url", name),
+        });
+    };
+
+    let args = config.get("args").and_then(Value::as_array).map(|a| {
+        a.iter().filter_map(|v| v.as_str().map(String::from)).collect()
+    });
+    let env = config.get("env").and_then(Value::as_object).map(|m| {
+        m.iter()
+            .filter_map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
+            .collect()
+    });
+
+    Ok(AddMCPServerRequest {
+        name: name.to_string(),
+        transport: transport.to_string(),
+        command,
+        args,
+        env,
+        url,
+        scope: Some("user".to_string()),
+        project_path: None,
+    })
 }
\ No newline at end of file
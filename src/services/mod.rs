@@ -1,7 +1,13 @@
 pub mod database;
 pub mod claude;
 pub mod mcp;
+pub mod postgres_store;
+pub mod remote;
+pub mod sqlite_store;
+pub mod store;
 
 pub use database::DatabaseService;
 pub use claude::ClaudeService;
-pub use mcp::MCPService;
\ No newline at end of file
+pub use mcp::MCPService;
+pub use remote::{RemoteAuth, RemoteHost};
+pub use store::{AgentStore, SessionStore, Store};
\ No newline at end of file
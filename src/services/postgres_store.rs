@@ -0,0 +1,265 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use deadpool_postgres::{Config as PoolConfig, Pool, Runtime};
+use tokio_postgres::NoTls;
+
+use crate::models::{
+    agent::{Agent, CreateAgentRequest},
+    claude::SessionRecord,
+};
+
+use super::store::{AgentStore, SessionStore};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS agents (
+        id BIGSERIAL PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE,
+        icon TEXT NOT NULL,
+        system_prompt TEXT NOT NULL,
+        default_task TEXT,
+        model TEXT NOT NULL,
+        hooks TEXT,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+    );
+
+    CREATE TABLE IF NOT EXISTS agent_runs (
+        id BIGSERIAL PRIMARY KEY,
+        agent_id BIGINT NOT NULL REFERENCES agents (id) ON DELETE CASCADE,
+        task TEXT NOT NULL,
+        model TEXT NOT NULL,
+        project_path TEXT NOT NULL,
+        session_id TEXT NOT NULL,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+        status TEXT NOT NULL DEFAULT 'running',
+        output TEXT
+    );
+
+    ALTER TABLE agent_runs ADD COLUMN IF NOT EXISTS request_id TEXT;
+";
+
+/// A Postgres-backed implementation of [`AgentStore`]/[`SessionStore`],
+/// selected when `DatabaseConfig.backend` is `Postgres` so self-hosted,
+/// multi-instance deployments can point every instance at one shared
+/// database instead of each keeping its own SQLite file.
+///
+/// Only agents and sessions live here today — API tokens and project
+/// bookkeeping haven't been ported off SQLite (see [`super::DatabaseService`]).
+pub struct PostgresStore {
+    pool: Pool,
+}
+
+impl PostgresStore {
+    /// Connect to `url` and ensure the schema exists. Runs the one-time
+    /// async setup via `block_in_place`/`block_on` so the constructor can
+    /// stay synchronous like [`super::sqlite_store::SqliteStore::new`],
+    /// which callers rely on to build `DatabaseService` without an `.await`.
+    pub fn new(url: &str, max_connections: u32) -> Result<Self> {
+        let mut cfg = PoolConfig::new();
+        cfg.url = Some(url.to_string());
+        cfg.pool = Some(deadpool_postgres::PoolConfig::new(max_connections as usize));
+
+        let pool = cfg
+            .create_pool(Some(Runtime::Tokio1), NoTls)
+            .context("failed to build the Postgres connection pool")?;
+
+        let store = PostgresStore { pool };
+        tokio::task::block_in_place(|| {
+            tokio::runtime::Handle::current().block_on(store.init_schema())
+        })?;
+
+        Ok(store)
+    }
+
+    async fn init_schema(&self) -> Result<()> {
+        let conn = self.pool.get().await?;
+        conn.batch_execute(SCHEMA).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AgentStore for PostgresStore {
+    async fn create_agent(&self, request: CreateAgentRequest) -> Result<Agent> {
+        let conn = self.pool.get().await?;
+        let row = conn
+            .query_one(
+                "INSERT INTO agents (name, icon, system_prompt, default_task, model, hooks)
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 RETURNING id, created_at, updated_at",
+                &[
+                    &request.name,
+                    &request.icon,
+                    &request.system_prompt,
+                    &request.default_task,
+                    &request.model,
+                    &request.hooks,
+                ],
+            )
+            .await?;
+
+        Ok(Agent {
+            id: Some(row.get::<_, i64>(0)),
+            name: request.name,
+            icon: request.icon,
+            system_prompt: request.system_prompt,
+            default_task: request.default_task,
+            model: request.model,
+            hooks: request.hooks,
+            created_at: row.get::<_, chrono::DateTime<chrono::Utc>>(1).to_rfc3339(),
+            updated_at: row.get::<_, chrono::DateTime<chrono::Utc>>(2).to_rfc3339(),
+        })
+    }
+
+    async fn get_agents(&self) -> Result<Vec<Agent>> {
+        let conn = self.pool.get().await?;
+        let rows = conn
+            .query(
+                "SELECT id, name, icon, system_prompt, default_task, model, hooks, created_at, updated_at
+                 FROM agents ORDER BY created_at DESC",
+                &[],
+            )
+            .await?;
+
+        Ok(rows.iter().map(row_to_agent).collect())
+    }
+
+    async fn get_agent(&self, id: i64) -> Result<Option<Agent>> {
+        let conn = self.pool.get().await?;
+        let row = conn
+            .query_opt(
+                "SELECT id, name, icon, system_prompt, default_task, model, hooks, created_at, updated_at
+                 FROM agents WHERE id = $1",
+                &[&id],
+            )
+            .await?;
+
+        Ok(row.as_ref().map(row_to_agent))
+    }
+
+    async fn delete_agent(&self, id: i64) -> Result<bool> {
+        let conn = self.pool.get().await?;
+        let affected = conn.execute("DELETE FROM agents WHERE id = $1", &[&id]).await?;
+        Ok(affected > 0)
+    }
+}
+
+#[async_trait]
+impl SessionStore for PostgresStore {
+    async fn create_session_record(
+        &self,
+        session_id: &str,
+        task: &str,
+        project_path: &str,
+        model: &str,
+        request_id: Option<&str>,
+    ) -> Result<()> {
+        let conn = self.pool.get().await?;
+
+        // Mirror SqliteStore's bootstrap agent so a session can be recorded
+        // without the caller having created an `Agent` row first.
+        conn.execute(
+            "INSERT INTO agents (id, name, icon, system_prompt, model)
+             VALUES (1, 'Claude Code', 'ðŸ¤–', 'You are Claude Code CLI assistant', 'claude-3-5-sonnet-20241022')
+             ON CONFLICT (id) DO NOTHING",
+            &[],
+        )
+        .await?;
+
+        conn.execute(
+            "INSERT INTO agent_runs (agent_id, task, model, project_path, session_id, request_id)
+             VALUES (1, $1, $2, $3, $4, $5)",
+            &[&task, &model, &project_path, &session_id, &request_id],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_session_status(&self, session_id: &str, status: &str, output: Option<&str>) -> Result<()> {
+        let conn = self.pool.get().await?;
+
+        if let Some(output) = output {
+            conn.execute(
+                "UPDATE agent_runs SET status = $1, output = $2 WHERE session_id = $3",
+                &[&status, &output, &session_id],
+            )
+            .await?;
+        } else {
+            conn.execute(
+                "UPDATE agent_runs SET status = $1 WHERE session_id = $2",
+                &[&status, &session_id],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn get_sessions(&self, project_path: Option<&str>) -> Result<Vec<SessionRecord>> {
+        let conn = self.pool.get().await?;
+
+        let rows = match project_path {
+            Some(path) => {
+                conn.query(
+                    "SELECT id, task, model, project_path, session_id, created_at, status, output, request_id
+                     FROM agent_runs WHERE project_path = $1 ORDER BY created_at DESC",
+                    &[&path],
+                )
+                .await?
+            }
+            None => {
+                conn.query(
+                    "SELECT id, task, model, project_path, session_id, created_at, status, output, request_id
+                     FROM agent_runs ORDER BY created_at DESC",
+                    &[],
+                )
+                .await?
+            }
+        };
+
+        Ok(rows.iter().map(row_to_session).collect())
+    }
+
+    async fn get_session(&self, session_id: &str) -> Result<Option<SessionRecord>> {
+        let conn = self.pool.get().await?;
+        let row = conn
+            .query_opt(
+                "SELECT id, task, model, project_path, session_id, created_at, status, output, request_id
+                 FROM agent_runs WHERE session_id = $1",
+                &[&session_id],
+            )
+            .await?;
+
+        Ok(row.as_ref().map(row_to_session))
+    }
+}
+
+fn row_to_agent(row: &tokio_postgres::Row) -> Agent {
+    Agent {
+        id: Some(row.get(0)),
+        name: row.get(1),
+        icon: row.get(2),
+        system_prompt: row.get(3),
+        default_task: row.get(4),
+        model: row.get(5),
+        hooks: row.get(6),
+        created_at: row.get::<_, chrono::DateTime<chrono::Utc>>(7).to_rfc3339(),
+        updated_at: row.get::<_, chrono::DateTime<chrono::Utc>>(8).to_rfc3339(),
+    }
+}
+
+fn row_to_session(row: &tokio_postgres::Row) -> SessionRecord {
+    SessionRecord {
+        id: row.get(0),
+        task: row.get(1),
+        model: row.get(2),
+        project_path: row.get(3),
+        session_id: row.get(4),
+        created_at: row.get::<_, chrono::DateTime<chrono::Utc>>(5).to_rfc3339(),
+        status: row.get(6),
+        output: row.get(7),
+        request_id: row.get(8),
+        running: false,
+    }
+}
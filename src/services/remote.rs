@@ -0,0 +1,191 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use tokio::process::{Child, Command};
+
+/// How a [`RemoteHost`] authenticates over SSH.
+#[derive(Debug, Clone)]
+pub enum RemoteAuth {
+    Password(String),
+    KeyFile(PathBuf),
+}
+
+/// An SSH host a Claude Code session can be run against in place of the
+/// local machine, the way editors support "edit a remote directory over
+/// SSH."
+#[derive(Debug, Clone)]
+pub struct RemoteHost {
+    pub host: String,
+    pub user: String,
+    pub auth: RemoteAuth,
+    pub remote_home: PathBuf,
+}
+
+impl RemoteHost {
+    pub fn new(host: impl Into<String>, user: impl Into<String>, auth: RemoteAuth, remote_home: PathBuf) -> Self {
+        Self {
+            host: host.into(),
+            user: user.into(),
+            auth,
+            remote_home,
+        }
+    }
+
+    fn ssh_target(&self) -> String {
+        format!("{}@{}", self.user, self.host)
+    }
+}
+
+/// One entry decoded from a remote directory listing.
+#[derive(Debug, Clone)]
+pub struct RemoteDirEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// Output of a single non-interactive remote command.
+#[derive(Debug, Clone)]
+pub struct RemoteCommandOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Abstraction over "run a command on a host and get output/a streaming
+/// child process back", used by `ClaudeService` for sessions started
+/// against a [`RemoteHost`]; local sessions are spawned directly with
+/// `tokio::process::Command` and don't go through it.
+#[async_trait::async_trait]
+pub trait RemoteTransport: Send + Sync {
+    /// Run `command` to completion and collect its stdout/stderr.
+    async fn run_command(&self, host: &RemoteHost, command: &str) -> Result<RemoteCommandOutput>;
+
+    /// List the entries of a remote directory.
+    async fn read_dir(&self, host: &RemoteHost, path: &str) -> Result<Vec<RemoteDirEntry>>;
+
+    /// Spawn `command` on the host and return a handle whose stdout/stderr
+    /// are piped back, the same shape `tokio::process::Command` gives for a
+    /// local spawn.
+    async fn spawn_streaming(&self, host: &RemoteHost, command: &str) -> Result<Child>;
+
+    /// Copy a local file to `remote_path` on the host.
+    async fn upload_file(&self, host: &RemoteHost, local_path: &Path, remote_path: &str) -> Result<()>;
+}
+
+/// [`RemoteTransport`] backed by the system `ssh`/`scp` binaries,
+/// authenticating with either a password (via `sshpass`) or an identity
+/// file.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SshTransport;
+
+impl SshTransport {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn ssh_command(&self, host: &RemoteHost) -> Command {
+        let mut cmd = match &host.auth {
+            RemoteAuth::Password(password) => {
+                let mut cmd = Command::new("sshpass");
+                cmd.arg("-p").arg(password).arg("ssh");
+                cmd
+            }
+            RemoteAuth::KeyFile(key_path) => {
+                let mut cmd = Command::new("ssh");
+                cmd.arg("-i").arg(key_path);
+                cmd
+            }
+        };
+        cmd.arg("-o")
+            .arg("StrictHostKeyChecking=accept-new")
+            .arg(host.ssh_target());
+        cmd
+    }
+
+    fn scp_command(&self, host: &RemoteHost) -> Command {
+        match &host.auth {
+            RemoteAuth::Password(password) => {
+                let mut cmd = Command::new("sshpass");
+                cmd.arg("-p").arg(password).arg("scp");
+                cmd
+            }
+            RemoteAuth::KeyFile(key_path) => {
+                let mut cmd = Command::new("scp");
+                cmd.arg("-i").arg(key_path);
+                cmd
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl RemoteTransport for SshTransport {
+    async fn run_command(&self, host: &RemoteHost, command: &str) -> Result<RemoteCommandOutput> {
+        let output = self
+            .ssh_command(host)
+            .arg(command)
+            .output()
+            .await
+            .with_context(|| format!("failed to run `{command}` on {}", host.ssh_target()))?;
+
+        Ok(RemoteCommandOutput {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+
+    async fn read_dir(&self, host: &RemoteHost, path: &str) -> Result<Vec<RemoteDirEntry>> {
+        // `ls -1p` suffixes directories with '/', which is enough to tell
+        // them apart from session files without a second round trip.
+        let listing = self
+            .run_command(host, &format!("ls -1p {}", shell_quote(path)))
+            .await?;
+
+        if !listing.success {
+            return Ok(Vec::new());
+        }
+
+        Ok(listing
+            .stdout
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| RemoteDirEntry {
+                is_dir: line.ends_with('/'),
+                name: line.trim_end_matches('/').to_string(),
+            })
+            .collect())
+    }
+
+    async fn spawn_streaming(&self, host: &RemoteHost, command: &str) -> Result<Child> {
+        self.ssh_command(host)
+            .arg(command)
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to spawn `{command}` on {}", host.ssh_target()))
+    }
+
+    async fn upload_file(&self, host: &RemoteHost, local_path: &Path, remote_path: &str) -> Result<()> {
+        let status = self
+            .scp_command(host)
+            .arg("-o")
+            .arg("StrictHostKeyChecking=accept-new")
+            .arg(local_path)
+            .arg(format!("{}:{}", host.ssh_target(), remote_path))
+            .status()
+            .await
+            .with_context(|| format!("failed to upload {} to {}", local_path.display(), host.ssh_target()))?;
+
+        if !status.success() {
+            anyhow::bail!("scp exited with status {status}");
+        }
+
+        Ok(())
+    }
+}
+
+/// Wrap `value` in single quotes for inclusion in a remote shell command,
+/// escaping any embedded single quotes.
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
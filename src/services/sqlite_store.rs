@@ -0,0 +1,821 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use base64::Engine;
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::config::DatabaseConfig;
+use crate::models::{
+    agent::{Agent, CreateAgentRequest},
+    auth::{ApiToken, AuthPrincipal, TokenScope},
+    claude::{CreateProjectRequest, Project, SessionRecord, UpdateProjectRequest},
+};
+
+use super::store::{AgentStore, SessionStore};
+
+/// Maps a `rusqlite::Row` into `Self`, columns in `SELECT` order.
+/// Centralizes the column order for a type in one place, instead of every
+/// query site repeating its own `row.get(0)?..row.get(n)?`.
+trait FromRow: Sized {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self>;
+}
+
+impl FromRow for Agent {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Agent {
+            id: Some(row.get(0)?),
+            name: row.get(1)?,
+            icon: row.get(2)?,
+            system_prompt: row.get(3)?,
+            default_task: row.get(4)?,
+            model: row.get(5)?,
+            hooks: row.get(6)?,
+            created_at: row.get(7)?,
+            updated_at: row.get(8)?,
+        })
+    }
+}
+
+impl FromRow for SessionRecord {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(SessionRecord {
+            id: row.get(0)?,
+            task: row.get(1)?,
+            model: row.get(2)?,
+            project_path: row.get(3)?,
+            session_id: row.get(4)?,
+            created_at: row.get(5)?,
+            status: row.get(6)?,
+            output: row.get(7)?,
+            request_id: row.get(8)?,
+            running: false,
+        })
+    }
+}
+
+/// A `query_map`/`query_row` callback that delegates to `T::from_row`, so
+/// query sites read as `stmt.query_map([], row_extract::<Agent>)` instead of
+/// repeating the mapping closure inline.
+fn row_extract<T: FromRow>(row: &rusqlite::Row) -> rusqlite::Result<T> {
+    T::from_row(row)
+}
+
+/// A single schema change, applied at most once. `version` must be unique
+/// and [`MIGRATIONS`] must stay sorted by it — migrations run in list order
+/// and are recorded in `schema_migrations` as they succeed.
+struct Migration {
+    version: u32,
+    description: &'static str,
+    up: &'static str,
+}
+
+/// Every migration ever shipped, oldest first. Append new ones here; never
+/// edit or remove an already-released entry, since existing databases have
+/// already recorded it as applied.
+static MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    description: "create agents, agent_runs, mcp_servers, slash_commands, projects and api_tokens tables",
+    up: "
+        CREATE TABLE IF NOT EXISTS agents (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            icon TEXT NOT NULL,
+            system_prompt TEXT NOT NULL,
+            default_task TEXT,
+            model TEXT NOT NULL,
+            hooks TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS agent_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            agent_id INTEGER NOT NULL,
+            agent_name TEXT NOT NULL,
+            agent_icon TEXT NOT NULL,
+            task TEXT NOT NULL,
+            model TEXT NOT NULL,
+            project_path TEXT NOT NULL,
+            session_id TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            status TEXT NOT NULL DEFAULT 'running',
+            output TEXT,
+            FOREIGN KEY (agent_id) REFERENCES agents (id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS mcp_servers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            command TEXT NOT NULL,
+            args TEXT NOT NULL,
+            env TEXT,
+            enabled BOOLEAN NOT NULL DEFAULT 1,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS slash_commands (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            command TEXT NOT NULL,
+            description TEXT,
+            enabled BOOLEAN NOT NULL DEFAULT 1,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS projects (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            path TEXT NOT NULL UNIQUE,
+            description TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            last_accessed_at DATETIME
+        );
+
+        CREATE TABLE IF NOT EXISTS api_tokens (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            token_hash TEXT NOT NULL UNIQUE,
+            scope TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            last_used_at DATETIME,
+            revoked BOOLEAN NOT NULL DEFAULT 0
+        );
+    ",
+}, Migration {
+    version: 2,
+    description: "add request_id to agent_runs for HTTP-to-session request tracing",
+    up: "
+        ALTER TABLE agent_runs ADD COLUMN request_id TEXT;
+    ",
+}];
+
+/// A pooled, `Send + Sync` handle to the SQLite database. Every operation
+/// checks out a connection from `pool` and runs its queries on a blocking
+/// task, so concurrent requests no longer serialize behind one shared
+/// connection the way a single `Arc<Mutex<Connection>>` would, and async
+/// handlers never stall the runtime waiting on SQLite I/O.
+///
+/// Implements [`AgentStore`]/[`SessionStore`] so it can serve as
+/// `DatabaseConfig.backend`'s `sqlite` option, but also exposes API-token and
+/// project methods directly — those haven't been ported to a backend trait
+/// yet, so `DatabaseService` always keeps one `SqliteStore` around for them
+/// regardless of which backend agents/sessions are on.
+#[derive(Clone)]
+pub struct SqliteStore {
+    pool: Pool<SqliteConnectionManager>,
+}
+
+impl SqliteStore {
+    pub fn new(config: &DatabaseConfig) -> Result<Self> {
+        // Ensure parent directory exists
+        if let Some(parent) = config.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let manager = SqliteConnectionManager::file(&config.path);
+        let pool = Pool::builder()
+            .max_size(config.max_connections)
+            .connection_timeout(Duration::from_secs(config.connection_timeout))
+            .build(manager)?;
+
+        let store = SqliteStore { pool };
+
+        store.init_database()?;
+        Ok(store)
+    }
+
+    /// Run `f` with a pooled connection on a blocking task, so the caller's
+    /// async task never blocks on SQLite I/O or on waiting for a free
+    /// connection in the pool.
+    async fn with_conn<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Connection) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = pool.get()?;
+            f(&conn)
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("database task panicked: {}", e))?
+    }
+
+    /// Apply every migration in [`MIGRATIONS`] newer than the version
+    /// recorded in `schema_migrations`, each inside its own transaction, so a
+    /// failed migration can't leave the schema half-upgraded. Safe to call on
+    /// every startup: a fully up-to-date database just finds nothing to do.
+    fn init_database(&self) -> Result<()> {
+        let mut conn = self.pool.get()?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS schema_migrations (
+                version INTEGER PRIMARY KEY,
+                applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )?;
+
+        let current: u32 = conn
+            .query_row("SELECT COALESCE(MAX(version), 0) FROM schema_migrations", [], |row| row.get(0))?;
+
+        for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration.up)?;
+            tx.execute(
+                "INSERT INTO schema_migrations (version) VALUES (?1)",
+                params![migration.version],
+            )?;
+            tx.commit()?;
+            tracing::info!(
+                "Applied database migration {:04}: {}",
+                migration.version,
+                migration.description
+            );
+        }
+
+        Ok(())
+    }
+
+    /// The highest migration version currently applied to the database.
+    pub fn current_schema_version(&self) -> Result<u32> {
+        let conn = self.pool.get()?;
+        let version = conn.query_row(
+            "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(version)
+    }
+
+    // API token operations
+
+    /// Mint a new token, returning its id and the raw token string. The raw
+    /// token is never stored or retrievable again; only its hash is.
+    pub async fn create_api_token(&self, name: &str, scope: TokenScope) -> Result<(i64, String)> {
+        let name = name.to_string();
+        self.with_conn(move |conn| {
+            let now = chrono::Utc::now().to_rfc3339();
+            let raw_token = format!("opcode_{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+            let token_hash = hash_token(&raw_token);
+
+            conn.execute(
+                "INSERT INTO api_tokens (name, token_hash, scope, created_at) VALUES (?1, ?2, ?3, ?4)",
+                params![name, token_hash, scope.as_str(), now],
+            )?;
+
+            Ok((conn.last_insert_rowid(), raw_token))
+        })
+        .await
+    }
+
+    pub async fn list_api_tokens(&self) -> Result<Vec<ApiToken>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, name, scope, created_at, last_used_at, revoked
+                 FROM api_tokens ORDER BY created_at DESC",
+            )?;
+
+            let tokens = stmt
+                .query_map([], |row| {
+                    let scope: String = row.get(2)?;
+                    let revoked: i64 = row.get(5)?;
+                    Ok(ApiToken {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        scope: TokenScope::parse(&scope).unwrap_or(TokenScope::ReadOnly),
+                        created_at: row.get(3)?,
+                        last_used_at: row.get(4)?,
+                        revoked: revoked != 0,
+                    })
+                })?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            Ok(tokens)
+        })
+        .await
+    }
+
+    pub async fn revoke_api_token(&self, id: i64) -> Result<bool> {
+        self.with_conn(move |conn| {
+            let affected = conn.execute("UPDATE api_tokens SET revoked = 1 WHERE id = ?1", [id])?;
+            Ok(affected > 0)
+        })
+        .await
+    }
+
+    /// Validate a raw bearer token against its stored hash, returning the
+    /// caller's identity if it's known and not revoked, and bumping
+    /// `last_used_at` as a side effect.
+    pub async fn validate_api_token(&self, raw_token: &str) -> Result<Option<AuthPrincipal>> {
+        let token_hash = hash_token(raw_token);
+        self.with_conn(move |conn| {
+            let row: Option<(i64, String, String, i64)> = conn
+                .query_row(
+                    "SELECT id, name, scope, revoked FROM api_tokens WHERE token_hash = ?1",
+                    params![token_hash],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+                )
+                .optional()?;
+
+            let Some((id, name, scope, revoked)) = row else {
+                return Ok(None);
+            };
+
+            if revoked != 0 {
+                return Ok(None);
+            }
+
+            let now = chrono::Utc::now().to_rfc3339();
+            conn.execute("UPDATE api_tokens SET last_used_at = ?1 WHERE id = ?2", params![now, id])?;
+
+            Ok(Some(AuthPrincipal {
+                token_id: id,
+                name,
+                scope: TokenScope::parse(&scope).unwrap_or(TokenScope::ReadOnly),
+            }))
+        })
+        .await
+    }
+
+    // Project operations
+    pub async fn create_project(&self, request: CreateProjectRequest) -> Result<Project> {
+        // Generate project ID using base64 encoding of the path (to match Claude's convention)
+        let project_id = base64::engine::general_purpose::STANDARD.encode(&request.path);
+
+        // Validate that the parent directory exists
+        let parent_path = std::path::Path::new(&request.path).parent()
+            .ok_or_else(|| anyhow::anyhow!("Invalid path: no parent directory"))?;
+
+        if !parent_path.exists() {
+            return Err(anyhow::anyhow!("Parent directory does not exist: {}", parent_path.display()));
+        }
+
+        let id_for_insert = project_id.clone();
+        let name = request.name.clone();
+        let path = request.path.clone();
+        let description = request.description.clone();
+        self.with_conn(move |conn| {
+            let now = chrono::Utc::now().to_rfc3339();
+
+            conn.execute(
+                "INSERT INTO projects (id, name, path, description, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![id_for_insert, name, path, description, now, now],
+            )?;
+
+            Ok(())
+        })
+        .await?;
+
+        // Create the actual project directory
+        let project_path = std::path::Path::new(&request.path);
+        if !project_path.exists() {
+            std::fs::create_dir_all(project_path)?;
+        }
+
+        // Create the Claude project directory structure
+        self.create_claude_project_directory(&project_id, &request.path)?;
+
+        Ok(Project {
+            id: project_id,
+            path: request.path,
+            sessions: vec![], // New project has no sessions
+            created_at: chrono::Utc::now().timestamp() as u64,
+            most_recent_session: None,
+        })
+    }
+
+    pub async fn get_projects(&self) -> Result<Vec<Project>> {
+        let rows: Vec<(String, String, u64)> = self
+            .with_conn(|conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT id, name, path, description, created_at, updated_at, last_accessed_at
+                     FROM projects ORDER BY updated_at DESC"
+                )?;
+
+                let project_iter = stmt.query_map([], |row| {
+                    let id: String = row.get(0)?;
+                    let path: String = row.get(2)?;
+                    let created_at_str: String = row.get(4)?;
+
+                    // Parse created_at to timestamp
+                    let created_at = chrono::DateTime::parse_from_rfc3339(&created_at_str)
+                        .map(|dt| dt.timestamp() as u64)
+                        .unwrap_or(0);
+
+                    Ok((id, path, created_at))
+                })?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+                Ok(project_iter)
+            })
+            .await?;
+
+        let mut projects = Vec::new();
+        for (id, path, created_at) in rows {
+            // Get sessions for this project (this will be empty for database-managed projects)
+            // But we still check the Claude directory structure for compatibility
+            let sessions = self.get_project_sessions(&id)?;
+            let most_recent_session = self.get_most_recent_session_time(&id)?;
+
+            projects.push(Project {
+                id,
+                path,
+                sessions,
+                created_at,
+                most_recent_session,
+            });
+        }
+
+        Ok(projects)
+    }
+
+    pub async fn update_project(&self, project_id: &str, request: UpdateProjectRequest) -> Result<Option<Project>> {
+        let project_id = project_id.to_string();
+        let updated: Option<(String, String)> = self
+            .with_conn(move |conn| {
+                let now = chrono::Utc::now().to_rfc3339();
+
+                // First check if project exists
+                let existing: Option<(String, String, String)> = conn.query_row(
+                    "SELECT id, name, path FROM projects WHERE id = ?1",
+                    params![project_id],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                ).optional()?;
+
+                let (id, current_name, path) = match existing {
+                    Some(project) => project,
+                    None => return Ok(None), // Project not found
+                };
+
+                // Build update query dynamically based on provided fields
+                let mut update_parts = Vec::new();
+                let mut update_params: Vec<&dyn rusqlite::ToSql> = Vec::new();
+
+                let name = request.name.as_ref().unwrap_or(&current_name);
+                let description = request.description.as_deref().unwrap_or("");
+
+                update_parts.push("name = ?1");
+                update_params.push(name);
+
+                update_parts.push("description = ?2");
+                update_params.push(&description);
+
+                update_parts.push("updated_at = ?3");
+                update_params.push(&now);
+
+                // Add project_id as the last parameter for WHERE clause
+                update_params.push(&project_id);
+
+                let update_query = format!(
+                    "UPDATE projects SET {} WHERE id = ?{}",
+                    update_parts.join(", "),
+                    update_params.len()
+                );
+
+                conn.execute(&update_query, &update_params[..])?;
+
+                Ok(Some((id, path)))
+            })
+            .await?;
+
+        let (id, path) = match updated {
+            Some(project) => project,
+            None => return Ok(None),
+        };
+
+        // Return updated project
+        let sessions = self.get_project_sessions(&id)?;
+        let most_recent_session = self.get_most_recent_session_time(&id)?;
+        let created_at = chrono::Utc::now().timestamp() as u64; // This should be fetched from DB in real implementation
+
+        Ok(Some(Project {
+            id,
+            path,
+            sessions,
+            created_at,
+            most_recent_session,
+        }))
+    }
+
+    pub async fn delete_project(&self, project_id: &str) -> Result<bool> {
+        let project_id_owned = project_id.to_string();
+        let (rows_affected, project_path) = self
+            .with_conn(move |conn| {
+                // First get the project path before deleting from database
+                let project_path: Option<String> = conn.query_row(
+                    "SELECT path FROM projects WHERE id = ?1",
+                    params![project_id_owned],
+                    |row| row.get(0)
+                ).optional()?;
+
+                let rows_affected = conn.execute(
+                    "DELETE FROM projects WHERE id = ?1",
+                    params![project_id_owned],
+                )?;
+
+                Ok((rows_affected, project_path))
+            })
+            .await?;
+
+        // Remove the Claude project directory and actual project directory if they exist
+        if rows_affected > 0 {
+            self.remove_claude_project_directory(project_id)?;
+
+            // Also remove the actual project directory if it exists
+            if let Some(path) = project_path {
+                let project_dir = std::path::Path::new(&path);
+                if project_dir.exists() && project_dir.is_dir() {
+                    std::fs::remove_dir_all(project_dir)?;
+                }
+            }
+        }
+
+        Ok(rows_affected > 0)
+    }
+
+    fn create_claude_project_directory(&self, project_id: &str, _project_path: &str) -> Result<()> {
+        let claude_dir = dirs::home_dir()
+            .ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?
+            .join(".claude")
+            .join("projects")
+            .join(project_id);
+
+        std::fs::create_dir_all(&claude_dir)?;
+        Ok(())
+    }
+
+    fn remove_claude_project_directory(&self, project_id: &str) -> Result<()> {
+        let claude_dir = dirs::home_dir()
+            .ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?
+            .join(".claude")
+            .join("projects")
+            .join(project_id);
+
+        if claude_dir.exists() {
+            std::fs::remove_dir_all(&claude_dir)?;
+        }
+        Ok(())
+    }
+
+    fn get_project_sessions(&self, project_id: &str) -> Result<Vec<String>> {
+        let claude_dir = dirs::home_dir()
+            .ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?
+            .join(".claude")
+            .join("projects")
+            .join(project_id);
+
+        let mut sessions = Vec::new();
+
+        if claude_dir.exists() {
+            for entry in std::fs::read_dir(&claude_dir)? {
+                let entry = entry?;
+                let path = entry.path();
+                if path.extension().and_then(|ext| ext.to_str()) == Some("jsonl") {
+                    if let Some(session_name) = path.file_stem().and_then(|n| n.to_str()) {
+                        sessions.push(session_name.to_string());
+                    }
+                }
+            }
+        }
+
+        Ok(sessions)
+    }
+
+    fn get_most_recent_session_time(&self, project_id: &str) -> Result<Option<u64>> {
+        let claude_dir = dirs::home_dir()
+            .ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?
+            .join(".claude")
+            .join("projects")
+            .join(project_id);
+
+        if !claude_dir.exists() {
+            return Ok(None);
+        }
+
+        let mut most_recent = None;
+
+        for entry in std::fs::read_dir(&claude_dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) == Some("jsonl") {
+                if let Ok(metadata) = path.metadata() {
+                    if let Ok(created) = metadata.created() {
+                        if let Ok(duration) = created.duration_since(std::time::UNIX_EPOCH) {
+                            let timestamp = duration.as_secs();
+                            if most_recent.is_none() || most_recent.unwrap() < timestamp {
+                                most_recent = Some(timestamp);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(most_recent)
+    }
+}
+
+#[async_trait]
+impl AgentStore for SqliteStore {
+    async fn create_agent(&self, request: CreateAgentRequest) -> Result<Agent> {
+        self.with_conn(move |conn| {
+            let now = chrono::Utc::now().to_rfc3339();
+
+            conn.execute(
+                "INSERT INTO agents (name, icon, system_prompt, default_task, model,
+                 hooks, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    request.name,
+                    request.icon,
+                    request.system_prompt,
+                    request.default_task,
+                    request.model,
+                    request.hooks,
+                    now,
+                    now
+                ],
+            )?;
+
+            let id = conn.last_insert_rowid();
+
+            Ok(Agent {
+                id: Some(id),
+                name: request.name,
+                icon: request.icon,
+                system_prompt: request.system_prompt,
+                default_task: request.default_task,
+                model: request.model,
+                hooks: request.hooks,
+                created_at: now.clone(),
+                updated_at: now,
+            })
+        })
+        .await
+    }
+
+    async fn get_agents(&self) -> Result<Vec<Agent>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, name, icon, system_prompt, default_task, model,
+                 hooks, created_at, updated_at FROM agents ORDER BY created_at DESC"
+            )?;
+
+            let agents = stmt
+                .query_map([], row_extract::<Agent>)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            Ok(agents)
+        })
+        .await
+    }
+
+    async fn get_agent(&self, id: i64) -> Result<Option<Agent>> {
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, name, icon, system_prompt, default_task, model,
+                 hooks, created_at, updated_at FROM agents WHERE id = ?1"
+            )?;
+
+            let agent = stmt.query_row([id], row_extract::<Agent>).optional()?;
+
+            Ok(agent)
+        })
+        .await
+    }
+
+    async fn delete_agent(&self, id: i64) -> Result<bool> {
+        self.with_conn(move |conn| {
+            let affected = conn.execute("DELETE FROM agents WHERE id = ?1", [id])?;
+            Ok(affected > 0)
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl SessionStore for SqliteStore {
+    async fn create_session_record(
+        &self,
+        session_id: &str,
+        task: &str,
+        project_path: &str,
+        model: &str,
+        request_id: Option<&str>,
+    ) -> Result<()> {
+        let session_id = session_id.to_string();
+        let task = task.to_string();
+        let project_path = project_path.to_string();
+        let model = model.to_string();
+        let request_id = request_id.map(|r| r.to_string());
+        self.with_conn(move |conn| {
+            let now = chrono::Utc::now().to_rfc3339();
+
+            // Create a dummy agent first if it doesn't exist (agent_id = 1)
+            let _ = conn.execute(
+                "INSERT OR IGNORE INTO agents (id, name, icon, system_prompt, model, created_at, updated_at)
+                 VALUES (1, 'Claude Code', 'ðŸ¤–', 'You are Claude Code CLI assistant', 'claude-3-5-sonnet-20241022', ?1, ?2)",
+                params![now, now],
+            );
+
+            conn.execute(
+                "INSERT INTO agent_runs (agent_id, agent_name, agent_icon, task, model, project_path, session_id, created_at, request_id)
+                 VALUES (1, 'Claude Code', 'ðŸ¤–', ?1, ?2, ?3, ?4, ?5, ?6)",
+                params![task, model, project_path, session_id, now, request_id],
+            )?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn update_session_status(&self, session_id: &str, status: &str, output: Option<&str>) -> Result<()> {
+        let session_id = session_id.to_string();
+        let status = status.to_string();
+        let output = output.map(|o| o.to_string());
+        self.with_conn(move |conn| {
+            if let Some(output) = output {
+                conn.execute(
+                    "UPDATE agent_runs SET status = ?1, output = ?2 WHERE session_id = ?3",
+                    params![status, output, session_id],
+                )?;
+            } else {
+                conn.execute(
+                    "UPDATE agent_runs SET status = ?1 WHERE session_id = ?2",
+                    params![status, session_id],
+                )?;
+            }
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get_sessions(&self, project_path: Option<&str>) -> Result<Vec<SessionRecord>> {
+        let project_path = project_path.map(|p| p.to_string());
+        self.with_conn(move |conn| {
+            let mut sessions = Vec::new();
+
+            if let Some(path) = project_path {
+                let mut stmt = conn.prepare(
+                    "SELECT id, task, model, project_path, session_id, created_at, status, output, request_id
+                     FROM agent_runs WHERE project_path = ? ORDER BY created_at DESC"
+                )?;
+
+                let session_iter = stmt.query_map([path], row_extract::<SessionRecord>)?;
+
+                for session in session_iter {
+                    sessions.push(session?);
+                }
+            } else {
+                let mut stmt = conn.prepare(
+                    "SELECT id, task, model, project_path, session_id, created_at, status, output, request_id
+                     FROM agent_runs ORDER BY created_at DESC"
+                )?;
+
+                let session_iter = stmt.query_map([], row_extract::<SessionRecord>)?;
+
+                for session in session_iter {
+                    sessions.push(session?);
+                }
+            }
+
+            Ok(sessions)
+        })
+        .await
+    }
+
+    async fn get_session(&self, session_id: &str) -> Result<Option<SessionRecord>> {
+        let session_id = session_id.to_string();
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, task, model, project_path, session_id, created_at, status, output, request_id
+                 FROM agent_runs WHERE session_id = ?1"
+            )?;
+
+            let session = stmt
+                .query_row([session_id], row_extract::<SessionRecord>)
+                .optional()?;
+
+            Ok(session)
+        })
+        .await
+    }
+}
+
+/// Hash a raw API token for storage/comparison, so the database never holds
+/// a token in a form that's directly usable if the file leaked.
+fn hash_token(token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(token.as_bytes());
+    hex::encode(hasher.finalize())
+}
@@ -0,0 +1,44 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::models::{
+    agent::{Agent, CreateAgentRequest},
+    claude::SessionRecord,
+};
+
+/// Backend-agnostic storage for agents, implemented once per supported
+/// database so [`DatabaseService`](super::DatabaseService) can dispatch to
+/// whichever one `DatabaseConfig.backend` selects.
+#[async_trait]
+pub trait AgentStore: Send + Sync {
+    async fn create_agent(&self, request: CreateAgentRequest) -> Result<Agent>;
+    async fn get_agents(&self) -> Result<Vec<Agent>>;
+    async fn get_agent(&self, id: i64) -> Result<Option<Agent>>;
+    async fn delete_agent(&self, id: i64) -> Result<bool>;
+}
+
+/// Backend-agnostic storage for Claude Code session records.
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    async fn create_session_record(
+        &self,
+        session_id: &str,
+        task: &str,
+        project_path: &str,
+        model: &str,
+        request_id: Option<&str>,
+    ) -> Result<()>;
+    async fn update_session_status(
+        &self,
+        session_id: &str,
+        status: &str,
+        output: Option<&str>,
+    ) -> Result<()>;
+    async fn get_sessions(&self, project_path: Option<&str>) -> Result<Vec<SessionRecord>>;
+    async fn get_session(&self, session_id: &str) -> Result<Option<SessionRecord>>;
+}
+
+/// A database backend that can serve both agent and session storage — the
+/// unit `DatabaseConfig.backend` actually selects between.
+pub trait Store: AgentStore + SessionStore {}
+impl<T: AgentStore + SessionStore> Store for T {}
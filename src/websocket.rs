@@ -1,27 +1,153 @@
 use axum::{
     extract::{
-        ws::{Message, WebSocket, WebSocketUpgrade},
-        State, Path,
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        Query, State, Path,
+    },
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Response,
     },
-    response::Response,
     routing::get,
     Router,
 };
 use futures::{sink::SinkExt, stream::StreamExt};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet, VecDeque},
+    convert::Infallible,
+    path::PathBuf,
     process::Stdio,
     sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use tokio::{
-    io::{AsyncBufReadExt, BufReader},
+    fs,
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
     process::Command,
-    sync::{broadcast, Mutex},
+    sync::{broadcast, mpsc, Mutex},
 };
+use tokio_stream::{wrappers::BroadcastStream, Stream};
+use tokio_util::io::ReaderStream;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
+use crate::auth::TokenAuthenticator;
+use crate::models::claude::{classify_stream_json, StreamEvent};
+
+/// Default lifetime of a per-session token minted by `POST /claude/token`.
+const SESSION_TOKEN_TTL_SECS: u64 = 60 * 15;
+
+/// Number of recent output lines retained per session for replay to
+/// newly-(re)connecting subscribers.
+const REPLAY_BUFFER_CAPACITY: usize = 1000;
+
+/// A bounded, sequence-numbered ring buffer of a session's recent output,
+/// used to replay lines a subscriber missed instead of requiring it to have
+/// been connected for the entire run.
+#[derive(Default)]
+struct ReplayBuffer {
+    next_seq: u64,
+    lines: VecDeque<(u64, String)>,
+}
+
+impl ReplayBuffer {
+    /// Record a line, returning the sequence number assigned to it.
+    fn push(&mut self, line: &str) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.lines.push_back((seq, line.to_string()));
+        if self.lines.len() > REPLAY_BUFFER_CAPACITY {
+            self.lines.pop_front();
+        }
+        seq
+    }
+
+    /// All buffered lines with a sequence number greater than `since`.
+    fn since(&self, since: u64) -> Vec<(u64, String)> {
+        self.lines
+            .iter()
+            .filter(|(seq, _)| *seq > since)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Wrap a line and its sequence number into the envelope sent over the wire,
+/// so a subscriber can tell replayed and live frames apart from duplicates.
+fn frame(seq: u64, payload: &str) -> String {
+    serde_json::json!({ "seq": seq, "payload": payload }).to_string()
+}
+
+/// Default size of the worker pool that runs queued Claude Code jobs.
+const DEFAULT_MAX_CONCURRENT_JOBS: usize = 4;
+
+/// Capacity of the job queue; beyond this, `enqueue` would need to apply
+/// backpressure, but in practice the queue drains faster than it could fill.
+const JOB_QUEUE_CAPACITY: usize = 256;
+
+/// Lifecycle state of a queued or running Claude Code job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    fn as_str(&self) -> &'static str {
+        match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Completed => "completed",
+            JobState::Failed => "failed",
+            JobState::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// A Claude Code execution waiting to be picked up by a worker.
+struct Job {
+    session_id: String,
+    request: ExecuteRequest,
+}
+
+/// Directory a session's durable artifacts (`stdout.log`, `stderr.log`,
+/// `events.jsonl`, `meta.json`) are written to, so output survives past
+/// `remove_session` and can be re-fetched after the client disconnects.
+///
+/// `pub(crate)` so `ClaudeService`'s session supervisor writes its own
+/// sessions' artifacts to the same place rather than inventing a second
+/// directory convention.
+pub(crate) fn session_artifacts_dir(session_id: &str) -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_default()
+        .join(".opcode")
+        .join("sessions")
+        .join(session_id)
+}
+
+/// Reject anything that isn't a bare UUID-shaped path segment. `session_id`
+/// is attacker-controlled (a client picks it on `SessionType::Resume`) and
+/// feeds straight into [`session_artifacts_dir`]/`PathBuf::join`, so without
+/// this a caller could pass an absolute path or a `..` segment and read
+/// arbitrary files on the host.
+fn is_valid_session_id(session_id: &str) -> bool {
+    !session_id.is_empty()
+        && session_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
 /// WebSocket manager to handle Claude Code sessions
 #[derive(Clone)]
 pub struct WebSocketManager {
@@ -29,33 +155,547 @@ pub struct WebSocketManager {
     sessions: Arc<Mutex<HashMap<String, broadcast::Sender<String>>>>,
     /// Running Claude processes by session ID
     processes: Arc<Mutex<HashMap<String, tokio::process::Child>>>,
+    /// Recent output per session, kept around for reconnect replay
+    buffers: Arc<Mutex<HashMap<String, ReplayBuffer>>>,
+    /// Issues and validates per-session bearer tokens
+    authenticator: TokenAuthenticator,
+    /// Current lifecycle state of every known job
+    job_states: Arc<Mutex<HashMap<String, JobState>>>,
+    /// Sessions cancelled while still `Queued`, so the worker that eventually
+    /// pops them knows to skip rather than run them
+    cancelled_queued: Arc<Mutex<HashSet<String>>>,
+    /// Feeds the fixed-size worker pool spawned in `with_max_concurrent`
+    job_tx: mpsc::Sender<Job>,
+    /// Running (input_tokens, output_tokens, cost_usd) tally per session,
+    /// accumulated from `Usage`/`Result` stream events
+    cost_tallies: Arc<Mutex<HashMap<String, (u64, u64, f64)>>>,
+    /// Explicit Claude binary path from config, tried before PATH/per-OS
+    /// fallbacks (the `OPCODE_CLAUDE_BINARY` env var still wins over this)
+    claude_binary_override: Option<String>,
+    /// The Claude binary resolved by [`Self::resolve_claude_binary`], cached
+    /// so it's probed once rather than on every job
+    claude_binary: Arc<Mutex<Option<ClaudeBinaryInfo>>>,
 }
 
 impl WebSocketManager {
-    pub fn new() -> Self {
-        Self {
+    pub fn new(authenticator: TokenAuthenticator, claude_binary_override: Option<String>) -> Self {
+        Self::with_max_concurrent(authenticator, DEFAULT_MAX_CONCURRENT_JOBS, claude_binary_override)
+    }
+
+    /// Build a manager backed by a fixed pool of `max_concurrent` worker
+    /// tasks pulling from a bounded job queue, so a burst of callers queues
+    /// up behind a cap on concurrently running `claude` processes instead of
+    /// forking one per request.
+    pub fn with_max_concurrent(
+        authenticator: TokenAuthenticator,
+        max_concurrent: usize,
+        claude_binary_override: Option<String>,
+    ) -> Self {
+        let (job_tx, job_rx) = mpsc::channel(JOB_QUEUE_CAPACITY);
+        let job_rx = Arc::new(Mutex::new(job_rx));
+
+        let manager = Self {
             sessions: Arc::new(Mutex::new(HashMap::new())),
             processes: Arc::new(Mutex::new(HashMap::new())),
+            buffers: Arc::new(Mutex::new(HashMap::new())),
+            authenticator,
+            job_states: Arc::new(Mutex::new(HashMap::new())),
+            cancelled_queued: Arc::new(Mutex::new(HashSet::new())),
+            job_tx,
+            cost_tallies: Arc::new(Mutex::new(HashMap::new())),
+            claude_binary_override,
+            claude_binary: Arc::new(Mutex::new(None)),
+        };
+
+        for worker_id in 0..max_concurrent.max(1) {
+            let manager = manager.clone();
+            let job_rx = job_rx.clone();
+            tokio::spawn(async move {
+                loop {
+                    let job = job_rx.lock().await.recv().await;
+                    match job {
+                        Some(job) => manager.run_job(job).await,
+                        None => break,
+                    }
+                }
+                info!("Claude job worker {} shutting down", worker_id);
+            });
+        }
+
+        manager
+    }
+
+    /// Enqueue a Claude Code execution, returning its position in the queue
+    /// (0 = next to run). Fails only if the worker pool has shut down.
+    pub async fn enqueue(&self, session_id: String, request: ExecuteRequest) -> Result<usize, ()> {
+        let position = {
+            let mut states = self.job_states.lock().await;
+            let position = states
+                .values()
+                .filter(|state| **state == JobState::Queued)
+                .count();
+            states.insert(session_id.clone(), JobState::Queued);
+            position
+        };
+
+        self.emit(
+            &session_id,
+            &serde_json::json!({ "type": JobState::Queued.as_str(), "position": position }).to_string(),
+        )
+        .await;
+
+        self.job_tx
+            .send(Job { session_id, request })
+            .await
+            .map_err(|_| ())?;
+
+        Ok(position)
+    }
+
+    /// Resolve (probing the filesystem and invoking `claude --version` if
+    /// this is the first call) and return the Claude binary this server
+    /// will use. Subsequent calls just return the cached value.
+    pub async fn resolve_claude_binary(&self) -> Result<ClaudeBinaryInfo, ClaudeBinaryError> {
+        if let Some(info) = self.claude_binary.lock().await.clone() {
+            return Ok(info);
+        }
+
+        let info = discover_claude_binary(self.claude_binary_override.as_deref()).await?;
+        *self.claude_binary.lock().await = Some(info.clone());
+        Ok(info)
+    }
+
+    /// A snapshot of the cached Claude binary info, without probing if
+    /// discovery hasn't happened yet. Used by the health handler so it never
+    /// blocks on a process spawn.
+    pub async fn cached_claude_binary(&self) -> Option<ClaudeBinaryInfo> {
+        self.claude_binary.lock().await.clone()
+    }
+
+    /// Current lifecycle state of a job, if it's known to this manager.
+    pub async fn job_state(&self, session_id: &str) -> Option<JobState> {
+        self.job_states.lock().await.get(session_id).copied()
+    }
+
+    async fn set_job_state(&self, session_id: &str, state: JobState) {
+        self.job_states
+            .lock()
+            .await
+            .insert(session_id.to_string(), state);
+    }
+
+    /// Cancel a job regardless of whether it's still queued or already
+    /// running. A queued job is marked for the worker to skip; a running one
+    /// has its process killed via [`Self::cancel_process`].
+    pub async fn cancel_job(&self, session_id: &str) -> bool {
+        match self.job_state(session_id).await {
+            Some(JobState::Queued) => {
+                self.cancelled_queued.lock().await.insert(session_id.to_string());
+                self.set_job_state(session_id, JobState::Cancelled).await;
+                self.emit(
+                    session_id,
+                    &serde_json::json!({ "type": JobState::Cancelled.as_str(), "session_id": session_id }).to_string(),
+                )
+                .await;
+                true
+            }
+            Some(JobState::Running) => {
+                let killed = self.cancel_process(session_id).await;
+                if killed {
+                    self.set_job_state(session_id, JobState::Cancelled).await;
+                    self.emit(
+                        session_id,
+                        &serde_json::json!({ "type": "cancelled", "session_id": session_id }).to_string(),
+                    )
+                    .await;
+                }
+                killed
+            }
+            _ => false,
         }
     }
 
-    /// Register a new WebSocket session
-    pub async fn register_session(&self, session_id: String) -> broadcast::Receiver<String> {
-        let (tx, rx) = broadcast::channel(1000);
-        self.sessions.lock().await.insert(session_id, tx);
-        rx
+    /// Run one job end-to-end: spawn the `claude` process, stream its output,
+    /// and block until it exits, so the worker that owns this job doesn't
+    /// pick up another one until it's done. This is what actually enforces
+    /// `max_concurrent`.
+    async fn run_job(&self, job: Job) {
+        let Job { session_id, request } = job;
+
+        if self.cancelled_queued.lock().await.remove(&session_id) {
+            info!("Skipping cancelled queued job for session: {}", session_id);
+            return;
+        }
+
+        self.set_job_state(&session_id, JobState::Running).await;
+        self.emit(
+            &session_id,
+            &serde_json::json!({ "type": JobState::Running.as_str(), "session_id": session_id }).to_string(),
+        )
+        .await;
+
+        if let Err(message) = self.spawn_and_stream(&session_id, request).await {
+            error!("Job failed for session {}: {}", session_id, message);
+            self.set_job_state(&session_id, JobState::Failed).await;
+            self.emit(
+                &session_id,
+                &serde_json::json!({ "type": "error", "message": message }).to_string(),
+            )
+            .await;
+        }
     }
 
-    /// Send message to a specific session
-    pub async fn send_to_session(&self, session_id: &str, message: String) {
+    /// Build the `claude` invocation for `request`, spawn it, stream its
+    /// stdout/stderr, and block until it exits. This is the body that used
+    /// to live directly in the `POST /claude/execute` handler before jobs
+    /// were queued onto a worker pool; moving it here lets `run_job` await
+    /// the whole lifecycle so the pool's concurrency cap is real.
+    async fn spawn_and_stream(&self, session_id: &str, request: ExecuteRequest) -> Result<(), String> {
+        let claude_binary = self
+            .resolve_claude_binary()
+            .await
+            .map_err(|e| format!("Failed to find Claude binary: {e}"))?;
+
+        let artifacts_dir = session_artifacts_dir(session_id);
+        fs::create_dir_all(&artifacts_dir)
+            .await
+            .map_err(|e| format!("Failed to create artifacts directory: {e}"))?;
+
+        let mut args = Vec::new();
+        match request.session_type {
+            SessionType::New => {
+                args.push("-p".to_string());
+                args.push(request.prompt.clone());
+            }
+            SessionType::Continue => {
+                args.push("-c".to_string());
+                args.push("-p".to_string());
+                args.push(request.prompt.clone());
+            }
+            SessionType::Resume => {
+                args.push("--resume".to_string());
+                args.push(session_id.to_string());
+                args.push("-p".to_string());
+                args.push(request.prompt.clone());
+            }
+        }
+
+        args.extend([
+            "--model".to_string(),
+            request.model.clone(),
+            "--output-format".to_string(),
+            "stream-json".to_string(),
+            "--verbose".to_string(),
+            "--dangerously-skip-permissions".to_string(),
+        ]);
+
+        let mut cmd = Command::new(&claude_binary.path);
+        cmd.args(args)
+            .current_dir(&request.project_path)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let mut child = cmd.spawn().map_err(|e| format!("Failed to spawn Claude process: {e}"))?;
+
+        let stdout = child.stdout.take().ok_or("Failed to get stdout")?;
+        let stderr = child.stderr.take().ok_or("Failed to get stderr")?;
+
+        let pid = child.id().unwrap_or(0);
+        info!("Spawned Claude process with PID: {} for session: {}", pid, session_id);
+
+        self.store_process(session_id.to_string(), child).await;
+
+        let stdout_log = fs::File::create(artifacts_dir.join("stdout.log"))
+            .await
+            .map_err(|e| format!("Failed to create stdout.log: {e}"))?;
+        let stderr_log = fs::File::create(artifacts_dir.join("stderr.log"))
+            .await
+            .map_err(|e| format!("Failed to create stderr.log: {e}"))?;
+        let events_log = fs::File::create(artifacts_dir.join("events.jsonl"))
+            .await
+            .map_err(|e| format!("Failed to create events.jsonl: {e}"))?;
+
+        let manager_clone = self.clone();
+        let session_id_clone = session_id.to_string();
+        let stdout_task = tokio::spawn(async move {
+            let mut stdout_reader = BufReader::new(stdout).lines();
+            let mut stdout_log = stdout_log;
+            let mut events_log = events_log;
+            // Buffers a JSON object across reads in case Claude's
+            // `stream-json` output gets split across more than one line.
+            let mut pending = String::new();
+            while let Ok(Some(line)) = stdout_reader.next_line().await {
+                let _ = stdout_log.write_all(line.as_bytes()).await;
+                let _ = stdout_log.write_all(b"\n").await;
+
+                if pending.is_empty() {
+                    pending.push_str(&line);
+                } else {
+                    pending.push('\n');
+                    pending.push_str(&line);
+                }
+
+                let event = match serde_json::from_str::<serde_json::Value>(&pending) {
+                    Ok(value) => {
+                        let event = classify_stream_json(&value);
+                        pending.clear();
+                        Some(event)
+                    }
+                    Err(e) if e.is_eof() => {
+                        // Likely a JSON object split across lines; keep accumulating.
+                        None
+                    }
+                    Err(_) => {
+                        // Not a complete/valid JSON object even with more context; surface
+                        // it as-is rather than silently dropping Claude's output.
+                        Some(StreamEvent::Raw {
+                            raw: serde_json::Value::String(std::mem::take(&mut pending)),
+                        })
+                    }
+                };
+
+                if let Some(event) = event {
+                    if let Ok(serialized) = serde_json::to_string(&event) {
+                        let _ = events_log.write_all(serialized.as_bytes()).await;
+                        let _ = events_log.write_all(b"\n").await;
+                    }
+                    manager_clone.emit_event(&session_id_clone, &event).await;
+                }
+            }
+            info!("Stdout reading completed for session: {}", session_id_clone);
+        });
+
+        let manager_clone = self.clone();
+        let session_id_clone = session_id.to_string();
+        let stderr_task = tokio::spawn(async move {
+            let mut stderr_reader = BufReader::new(stderr).lines();
+            let mut stderr_log = stderr_log;
+            while let Ok(Some(line)) = stderr_reader.next_line().await {
+                let _ = stderr_log.write_all(line.as_bytes()).await;
+                let _ = stderr_log.write_all(b"\n").await;
+                let error_msg = serde_json::json!({
+                    "type": "error",
+                    "message": line
+                });
+                manager_clone.emit(&session_id_clone, &error_msg.to_string()).await;
+            }
+            info!("Stderr reading completed for session: {}", session_id_clone);
+        });
+
+        let started_at = now_unix();
+
+        // Poll for the process's exit while leaving it in `processes`, so
+        // `cancel_process` can still find and kill it, and so this worker
+        // stays occupied for the job's full lifetime rather than freeing up
+        // (and breaking the concurrency cap) the moment it spawns.
+        let waited = loop {
+            let polled = match self.processes.lock().await.get_mut(session_id) {
+                Some(process) => process.try_wait(),
+                None => break None,
+            };
+            match polled {
+                Ok(None) => tokio::time::sleep(Duration::from_millis(200)).await,
+                done => {
+                    self.processes.lock().await.remove(session_id);
+                    break Some(done.map(|status| status.expect("Ok(None) handled above")));
+                }
+            }
+        };
+        let outcome = if let Some(waited) = waited {
+            match waited {
+                Ok(status) => {
+                    let _ = stdout_task.await;
+                    let _ = stderr_task.await;
+                    let final_seq = self.peek_next_seq(session_id).await;
+                    let completion_msg = serde_json::json!({
+                        "type": "complete",
+                        "success": status.success(),
+                        "code": status.code(),
+                        "final_seq": final_seq,
+                    });
+                    self.emit(session_id, &completion_msg.to_string()).await;
+                    self.set_job_state(
+                        session_id,
+                        if status.success() { JobState::Completed } else { JobState::Failed },
+                    )
+                    .await;
+                    info!("Claude process completed with status: {} for session: {}", status, session_id);
+                    Some((status.success(), status.code()))
+                }
+                Err(e) => {
+                    let error_msg = serde_json::json!({
+                        "type": "error",
+                        "message": format!("Process error: {}", e)
+                    });
+                    self.emit(session_id, &error_msg.to_string()).await;
+                    self.set_job_state(session_id, JobState::Failed).await;
+                    error!("Claude process error for session {}: {}", session_id, e);
+                    None
+                }
+            }
+        } else {
+            // The process was already removed (e.g. cancelled mid-run);
+            // nothing left to wait on.
+            let _ = stdout_task.await;
+            let _ = stderr_task.await;
+            None
+        };
+
+        let finished_at = now_unix();
+        let meta = serde_json::json!({
+            "session_id": session_id,
+            "status": match outcome {
+                Some((true, _)) => "completed",
+                Some((false, _)) => "failed",
+                None => "unknown",
+            },
+            "exit_code": outcome.and_then(|(_, code)| code),
+            "model": request.model,
+            "prompt": request.prompt,
+            "project_path": request.project_path,
+            "started_at": started_at,
+            "finished_at": finished_at,
+            "duration_secs": finished_at.saturating_sub(started_at),
+        });
+        if let Ok(serialized) = serde_json::to_vec_pretty(&meta) {
+            let _ = fs::write(artifacts_dir.join("meta.json"), serialized).await;
+        }
+
+        Ok(())
+    }
+
+    /// Mint a short-lived token authorizing access to `session_id`.
+    pub fn issue_token(&self, session_id: &str) -> String {
+        self.authenticator.issue(session_id, SESSION_TOKEN_TTL_SECS)
+    }
+
+    /// Check whether `token` authorizes access to `session_id`.
+    pub fn authorize(&self, session_id: &str, token: Option<&str>) -> bool {
+        match token {
+            Some(token) => self.authenticator.validate(session_id, token),
+            None => false,
+        }
+    }
+
+    /// Subscribe to a session's broadcast fan-out, creating the channel if
+    /// this is the first subscriber, and replay any buffered lines with a
+    /// sequence number greater than `since`. Calling this more than once for
+    /// the same `session_id` (e.g. once from the WebSocket handler and once
+    /// from the SSE handler) hands back independent receivers on the *same*
+    /// sender, so both transports observe the same output.
+    ///
+    /// Returns the replay frames (already `since`-filtered and formatted via
+    /// [`frame`]) followed by the live receiver to attach after they're sent.
+    pub async fn subscribe(
+        &self,
+        session_id: &str,
+        since: Option<u64>,
+    ) -> (Vec<String>, broadcast::Receiver<String>) {
+        let rx = {
+            let mut sessions = self.sessions.lock().await;
+            if let Some(tx) = sessions.get(session_id) {
+                tx.subscribe()
+            } else {
+                let (tx, rx) = broadcast::channel(1000);
+                sessions.insert(session_id.to_string(), tx);
+                rx
+            }
+        };
+
+        let replay = match since {
+            Some(since) => self
+                .buffers
+                .lock()
+                .await
+                .get(session_id)
+                .map(|buffer| buffer.since(since))
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(seq, line)| frame(seq, &line))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        (replay, rx)
+    }
+
+    /// Record `payload` in the session's replay buffer and broadcast it,
+    /// tagged with the sequence number it was assigned. Returns that
+    /// sequence number so callers (e.g. the final `complete` frame) can
+    /// report the last sequence a client should expect.
+    pub async fn emit(&self, session_id: &str, payload: &str) -> u64 {
+        let seq = self
+            .buffers
+            .lock()
+            .await
+            .entry(session_id.to_string())
+            .or_default()
+            .push(payload);
+
         if let Some(tx) = self.sessions.lock().await.get(session_id) {
-            let _ = tx.send(message);
+            let _ = tx.send(frame(seq, payload));
+        }
+
+        seq
+    }
+
+    /// Emit a typed [`StreamEvent`], same as [`Self::emit`] but for the
+    /// normalized protocol rather than a raw string. Usage/result events
+    /// also update and emit a running [`StreamEvent::CostTally`] so clients
+    /// can display spend live instead of accumulating it themselves.
+    pub async fn emit_event(&self, session_id: &str, event: &StreamEvent) -> u64 {
+        let seq = self
+            .emit(session_id, &serde_json::to_string(event).unwrap_or_default())
+            .await;
+
+        if let Some(tally) = self.update_cost_tally(session_id, event).await {
+            self.emit(session_id, &serde_json::to_string(&tally).unwrap_or_default())
+                .await;
         }
+
+        seq
+    }
+
+    async fn update_cost_tally(&self, session_id: &str, event: &StreamEvent) -> Option<StreamEvent> {
+        let mut tallies = self.cost_tallies.lock().await;
+        let tally = tallies.entry(session_id.to_string()).or_insert((0, 0, 0.0));
+
+        match event {
+            StreamEvent::Usage { input_tokens, output_tokens } => {
+                tally.0 += input_tokens;
+                tally.1 += output_tokens;
+            }
+            StreamEvent::Result { cost_usd: Some(cost), .. } => {
+                tally.2 += cost;
+            }
+            _ => return None,
+        }
+
+        Some(StreamEvent::CostTally {
+            input_tokens: tally.0,
+            output_tokens: tally.1,
+            cost_usd: tally.2,
+        })
+    }
+
+    /// The sequence number that the *next* call to [`Self::emit`] for this
+    /// session will assign, without recording anything. Used to pre-compute
+    /// the final sequence to embed in the `complete` frame itself.
+    pub async fn peek_next_seq(&self, session_id: &str) -> u64 {
+        self.buffers
+            .lock()
+            .await
+            .get(session_id)
+            .map(|buffer| buffer.next_seq)
+            .unwrap_or(0)
     }
 
     /// Remove a session
     pub async fn remove_session(&self, session_id: &str) {
         self.sessions.lock().await.remove(session_id);
+        self.buffers.lock().await.remove(session_id);
+        self.job_states.lock().await.remove(session_id);
+        self.cost_tallies.lock().await.remove(session_id);
         if let Some(mut process) = self.processes.lock().await.remove(session_id) {
             let _ = process.kill().await;
         }
@@ -105,37 +745,152 @@ pub enum SessionType {
     Resume,   // Resume specific session with --resume
 }
 
-/// Query parameters for WebSocket connection
-#[allow(dead_code)]
+/// Query parameters for WebSocket/SSE connection
 #[derive(Debug, Deserialize)]
 pub struct WebSocketQuery {
-    pub session_id: String,
+    pub token: Option<String>,
+    /// Replay buffered output with a sequence number greater than this
+    /// before attaching the live stream, so a reconnecting client doesn't
+    /// lose lines emitted while it was away.
+    pub since: Option<u64>,
 }
 
 /// WebSocket routes
+///
+/// The mutating `/claude/*` endpoints are gated by [`require_bearer_auth`];
+/// the WebSocket upgrade itself is gated separately inside
+/// `claude_websocket_handler` since it needs the per-session token, not the
+/// shared server secret.
 pub fn websocket_router() -> Router<Arc<WebSocketManager>> {
-    Router::new()
-        .route("/ws/claude/:session_id", get(claude_websocket_handler))
+    let claude_routes = Router::new()
         .route("/claude/execute", axum::routing::post(execute_claude_code))
         .route("/claude/cancel/:session_id", axum::routing::post(cancel_execution))
+        .route("/claude/token/:session_id", axum::routing::post(issue_session_token))
+        .route("/claude/sessions/:session_id/artifacts", axum::routing::get(list_session_artifacts))
+        .route("/claude/sessions/:session_id/artifacts/:name", axum::routing::get(get_session_artifact))
+        .route_layer(axum::middleware::from_fn(require_bearer_auth));
+
+    Router::new()
+        .route("/ws/claude/:session_id", get(claude_websocket_handler))
+        .route("/claude/stream/:session_id", get(claude_sse_handler))
+        .merge(claude_routes)
+}
+
+/// Require an `Authorization: Bearer <secret>` header matching the server's
+/// configured auth secret before a caller can start, cancel, or mint a token
+/// for a Claude session.
+async fn require_bearer_auth(
+    State(manager): State<Arc<WebSocketManager>>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Result<Response, StatusCode> {
+    let provided = request
+        .headers()
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match provided {
+        Some(token) if manager.authenticator.validate_server_secret(token) => {
+            Ok(next.run(request).await)
+        }
+        _ => Err(StatusCode::UNAUTHORIZED),
+    }
+}
+
+/// Issue a short-lived token authorizing access to an already-created session.
+async fn issue_session_token(
+    Path(session_id): Path<String>,
+    State(manager): State<Arc<WebSocketManager>>,
+) -> axum::response::Json<serde_json::Value> {
+    let token = manager.issue_token(&session_id);
+    axum::response::Json(serde_json::json!({
+        "session_id": session_id,
+        "token": token,
+        "expires_in": SESSION_TOKEN_TTL_SECS,
+    }))
 }
 
 /// WebSocket handler for Claude Code sessions
+///
+/// The client must present a token minted for this `session_id` via the
+/// `?token=` query parameter before the socket is upgraded; a mismatched or
+/// expired token is rejected with a close frame rather than ever reaching
+/// `handle_websocket`.
 pub async fn claude_websocket_handler(
     ws: WebSocketUpgrade,
     Path(session_id): Path<String>,
+    Query(query): Query<WebSocketQuery>,
     State(manager): State<Arc<WebSocketManager>>,
 ) -> Response {
+    if !manager.authorize(&session_id, query.token.as_deref()) {
+        warn!("Rejected WebSocket handshake for session {}: invalid or missing token", session_id);
+        return ws.on_upgrade(move |socket| reject_handshake(socket));
+    }
+
     info!("WebSocket connection established for session: {}", session_id);
-    
-    ws.on_upgrade(move |socket| handle_websocket(socket, session_id, manager))
+
+    ws.on_upgrade(move |socket| handle_websocket(socket, session_id, query.since, manager))
+}
+
+/// Close a socket immediately with a policy-violation frame for a failed handshake.
+async fn reject_handshake(mut socket: WebSocket) {
+    let _ = socket
+        .send(Message::Close(Some(CloseFrame {
+            code: axum::extract::ws::close_code::POLICY,
+            reason: "invalid or expired session token".into(),
+        })))
+        .await;
+}
+
+/// Server-Sent Events alternative to `claude_websocket_handler` for clients
+/// that can't use WebSockets (e.g. behind proxies that strip the Upgrade
+/// header). Gated by the same per-session token as the WebSocket handshake,
+/// and subscribes to the same broadcast fan-out so a single
+/// `execute_claude_code` run can feed WebSocket and SSE subscribers at once.
+pub async fn claude_sse_handler(
+    Path(session_id): Path<String>,
+    Query(query): Query<WebSocketQuery>,
+    State(manager): State<Arc<WebSocketManager>>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    if !manager.authorize(&session_id, query.token.as_deref()) {
+        warn!("Rejected SSE connection for session {}: invalid or missing token", session_id);
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    info!("SSE connection established for session: {}", session_id);
+
+    let (replay, receiver) = manager.subscribe(&session_id, query.since).await;
+    let replay_stream = futures::stream::iter(replay.into_iter().map(|frame| Ok(Event::default().data(frame))));
+    let live_stream = BroadcastStream::new(receiver)
+        .filter_map(|message| message.ok().map(|message| Ok(Event::default().data(message))));
+    let stream = replay_stream.chain(live_stream);
+
+    Ok(Sse::new(stream).keep_alive(
+        KeepAlive::new()
+            .interval(Duration::from_secs(15))
+            .text("keep-alive"),
+    ))
 }
 
 /// Handle WebSocket connection
-async fn handle_websocket(socket: WebSocket, session_id: String, manager: Arc<WebSocketManager>) {
-    let mut receiver = manager.register_session(session_id.clone()).await;
+async fn handle_websocket(
+    socket: WebSocket,
+    session_id: String,
+    since: Option<u64>,
+    manager: Arc<WebSocketManager>,
+) {
+    let (replay, mut receiver) = manager.subscribe(&session_id, since).await;
     let (mut sender, mut receiver_ws) = socket.split();
 
+    // Flush anything the client missed before attaching the live stream, so
+    // a reconnect with `?since=<seq>` doesn't lose output emitted in between.
+    for frame in replay {
+        if sender.send(Message::Text(frame)).await.is_err() {
+            return;
+        }
+    }
+
     // Task to forward messages from broadcast to WebSocket
     let session_id_clone = session_id.clone();
     let forward_task = tokio::spawn(async move {
@@ -181,157 +936,69 @@ async fn handle_websocket(socket: WebSocket, session_id: String, manager: Arc<We
     info!("WebSocket connection cleaned up for session: {}", session_id);
 }
 
-/// Execute Claude Code command
+/// Queue a Claude Code execution
+///
+/// Rather than spawning a `claude` process immediately, this enqueues the
+/// request onto `WebSocketManager`'s worker pool and returns right away with
+/// the session's queue position; the pool runs it once a worker frees up,
+/// bounding how many `claude` processes run concurrently.
 pub async fn execute_claude_code(
     State(manager): State<Arc<WebSocketManager>>,
     axum::extract::Json(request): axum::extract::Json<ExecuteRequest>,
 ) -> Result<axum::response::Json<serde_json::Value>, axum::http::StatusCode> {
-    info!("Executing Claude Code: {:?}", request);
+    info!("Queueing Claude Code execution: {:?}", request);
+
+    if let Err(e) = manager.resolve_claude_binary().await {
+        error!("Claude binary unavailable: {}", e);
+        return Err(match e {
+            ClaudeBinaryError::NotFound => axum::http::StatusCode::SERVICE_UNAVAILABLE,
+            ClaudeBinaryError::NotExecutable(_) => axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            ClaudeBinaryError::UnsupportedVersion(_) => axum::http::StatusCode::PRECONDITION_FAILED,
+        });
+    }
 
     // Generate session ID if not provided
     let session_id = match request.session_type {
-        SessionType::Resume => request.session_id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+        SessionType::Resume => request.session_id.clone().unwrap_or_else(|| Uuid::new_v4().to_string()),
         _ => Uuid::new_v4().to_string(),
     };
 
-    // Find Claude binary
-    let claude_path = find_claude_binary().map_err(|e| {
-        error!("Failed to find Claude binary: {}", e);
-        axum::http::StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    // Build command arguments
-    let mut args = Vec::new();
-    match request.session_type {
-        SessionType::New => {
-            args.push("-p".to_string());
-            args.push(request.prompt.clone());
-        }
-        SessionType::Continue => {
-            args.push("-c".to_string());
-            args.push("-p".to_string());
-            args.push(request.prompt.clone());
-        }
-        SessionType::Resume => {
-            args.push("--resume".to_string());
-            args.push(session_id.clone());
-            args.push("-p".to_string());
-            args.push(request.prompt.clone());
-        }
+    // `Resume` takes the session id straight from the client, which then
+    // flows into `session_artifacts_dir`; reject anything that isn't a bare
+    // UUID-shaped segment before it can be used to escape that directory.
+    if !is_valid_session_id(&session_id) {
+        return Err(axum::http::StatusCode::BAD_REQUEST);
     }
 
-    args.extend([
-        "--model".to_string(),
-        request.model.clone(),
-        "--output-format".to_string(),
-        "stream-json".to_string(),
-        "--verbose".to_string(),
-        "--dangerously-skip-permissions".to_string(),
-    ]);
-
-    // Create and spawn command
-    let mut cmd = Command::new(claude_path);
-    cmd.args(args)
-        .current_dir(&request.project_path)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped());
-
-    let mut child = cmd.spawn().map_err(|e| {
-        error!("Failed to spawn Claude process: {}", e);
-        axum::http::StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    // Get stdout and stderr
-    let stdout = child.stdout.take().ok_or_else(|| {
-        error!("Failed to get stdout");
-        axum::http::StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-    let stderr = child.stderr.take().ok_or_else(|| {
-        error!("Failed to get stderr");
-        axum::http::StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    let pid = child.id().unwrap_or(0);
-    info!("Spawned Claude process with PID: {} for session: {}", pid, session_id);
-
-    // Store the process
-    manager.store_process(session_id.clone(), child).await;
+    let queue_position = manager
+        .enqueue(session_id.clone(), request)
+        .await
+        .map_err(|_| {
+            error!("Job queue unavailable; rejecting execute request for session {}", session_id);
+            axum::http::StatusCode::SERVICE_UNAVAILABLE
+        })?;
 
-    // Spawn tasks to read stdout and stderr
-    let manager_clone = manager.clone();
-    let session_id_clone = session_id.clone();
-    tokio::spawn(async move {
-        let mut stdout_reader = BufReader::new(stdout).lines();
-        while let Ok(Some(line)) = stdout_reader.next_line().await {
-            manager_clone.send_to_session(&session_id_clone, line).await;
-        }
-        info!("Stdout reading completed for session: {}", session_id_clone);
-    });
-
-    let manager_clone = manager.clone();
-    let session_id_clone = session_id.clone();
-    tokio::spawn(async move {
-        let mut stderr_reader = BufReader::new(stderr).lines();
-        while let Ok(Some(line)) = stderr_reader.next_line().await {
-            let error_msg = serde_json::json!({
-                "type": "error",
-                "message": line
-            });
-            manager_clone.send_to_session(&session_id_clone, error_msg.to_string()).await;
-        }
-        info!("Stderr reading completed for session: {}", session_id_clone);
-    });
-
-    // Wait for process completion in background
-    let manager_clone = manager.clone();
-    let session_id_clone = session_id.clone();
-    tokio::spawn(async move {
-        // Remove from our process map and wait for completion
-        if let Some(mut process) = manager_clone.processes.lock().await.remove(&session_id_clone) {
-            match process.wait().await {
-                Ok(status) => {
-                    let completion_msg = serde_json::json!({
-                        "type": "complete",
-                        "success": status.success(),
-                        "code": status.code()
-                    });
-                    manager_clone.send_to_session(&session_id_clone, completion_msg.to_string()).await;
-                    info!("Claude process completed with status: {} for session: {}", status, session_id_clone);
-                }
-                Err(e) => {
-                    let error_msg = serde_json::json!({
-                        "type": "error",
-                        "message": format!("Process error: {}", e)
-                    });
-                    manager_clone.send_to_session(&session_id_clone, error_msg.to_string()).await;
-                    error!("Claude process error for session {}: {}", session_id_clone, e);
-                }
-            }
-        }
-    });
+    let token = manager.issue_token(&session_id);
 
     Ok(axum::response::Json(serde_json::json!({
         "session_id": session_id,
-        "status": "started",
-        "websocket_url": format!("/ws/claude/{}", session_id)
+        "status": JobState::Queued.as_str(),
+        "queue_position": queue_position,
+        "websocket_url": format!("/ws/claude/{}?token={}", session_id, token),
+        "token": token,
     })))
 }
 
 /// Cancel Claude Code execution
+///
+/// Handles both a job still waiting in the queue and one already running.
 pub async fn cancel_execution(
     Path(session_id): Path<String>,
     State(manager): State<Arc<WebSocketManager>>,
 ) -> Result<axum::response::Json<serde_json::Value>, axum::http::StatusCode> {
     info!("Cancelling execution for session: {}", session_id);
 
-    let cancelled = manager.cancel_process(&session_id).await;
-
-    // Send cancellation message to WebSocket clients
-    let cancel_msg = serde_json::json!({
-        "type": "cancelled",
-        "session_id": session_id
-    });
-    manager.send_to_session(&session_id, cancel_msg.to_string()).await;
+    let cancelled = manager.cancel_job(&session_id).await;
 
     Ok(axum::response::Json(serde_json::json!({
         "session_id": session_id,
@@ -339,35 +1006,204 @@ pub async fn cancel_execution(
     })))
 }
 
-/// Find Claude binary (similar to the Tauri version)
-fn find_claude_binary() -> Result<String, String> {
-    // Try to find claude binary in PATH
-    if let Ok(path) = which::which("claude") {
-        return Ok(path.to_string_lossy().to_string());
+/// List the artifact files persisted for a session (`stdout.log`,
+/// `stderr.log`, `events.jsonl`, `meta.json`), so a caller can discover what
+/// it can fetch via [`get_session_artifact`] after the session is gone.
+async fn list_session_artifacts(
+    Path(session_id): Path<String>,
+) -> Result<axum::response::Json<serde_json::Value>, axum::http::StatusCode> {
+    if !is_valid_session_id(&session_id) {
+        return Err(axum::http::StatusCode::BAD_REQUEST);
     }
 
-    // Try common installation locations
-    let common_paths = vec![
-        "/usr/local/bin/claude",
-        "/opt/homebrew/bin/claude",
-        "~/.local/bin/claude",
-    ];
+    let dir = session_artifacts_dir(&session_id);
+    let mut read_dir = fs::read_dir(&dir).await.map_err(|_| axum::http::StatusCode::NOT_FOUND)?;
 
-    for path in common_paths {
-        let expanded_path = if path.starts_with("~") {
-            if let Some(home) = dirs::home_dir() {
-                home.join(&path[2..])
-            } else {
-                continue;
+    let mut files = Vec::new();
+    while let Ok(Some(entry)) = read_dir.next_entry().await {
+        if let Some(name) = entry.file_name().to_str() {
+            files.push(name.to_string());
+        }
+    }
+
+    Ok(axum::response::Json(serde_json::json!({
+        "session_id": session_id,
+        "files": files,
+    })))
+}
+
+/// Stream a single artifact file back to the caller via axum's body
+/// streaming rather than buffering it fully into memory.
+async fn get_session_artifact(
+    Path((session_id, name)): Path<(String, String)>,
+) -> Result<Response, axum::http::StatusCode> {
+    // Artifacts are always a flat filename inside the session's directory;
+    // reject anything that could escape it.
+    if !is_valid_session_id(&session_id) || name.contains('/') || name.contains("..") {
+        return Err(axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    let path = session_artifacts_dir(&session_id).join(&name);
+    let file = fs::File::open(&path).await.map_err(|_| axum::http::StatusCode::NOT_FOUND)?;
+    let body = axum::body::Body::from_stream(ReaderStream::new(file));
+
+    Response::builder()
+        .header(axum::http::header::CONTENT_TYPE, "application/octet-stream")
+        .body(body)
+        .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Why a candidate Claude binary couldn't be used, so callers can map the
+/// failure to a meaningful HTTP status instead of a generic 500.
+#[derive(Debug, Clone)]
+pub enum ClaudeBinaryError {
+    /// No candidate path existed. (A candidate that existed but failed to
+    /// run is reported as [`Self::NotExecutable`] instead.)
+    NotFound,
+    /// A candidate existed but couldn't be run (permissions, not actually an
+    /// executable, produced output `--version` couldn't make sense of, etc.).
+    NotExecutable(String),
+    /// The binary ran but reported a version older than
+    /// [`MIN_SUPPORTED_VERSION`], which this server's stream-json handling
+    /// hasn't been validated against.
+    UnsupportedVersion(String),
+}
+
+impl std::fmt::Display for ClaudeBinaryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClaudeBinaryError::NotFound => {
+                write!(f, "Claude binary not found. Please install Claude Code CLI.")
             }
-        } else {
-            std::path::PathBuf::from(path)
-        };
+            ClaudeBinaryError::NotExecutable(detail) => {
+                write!(f, "Claude binary is not executable: {detail}")
+            }
+            ClaudeBinaryError::UnsupportedVersion(version) => {
+                write!(f, "Claude binary reported an unsupported version: {version}")
+            }
+        }
+    }
+}
 
-        if expanded_path.exists() {
-            return Ok(expanded_path.to_string_lossy().to_string());
+impl std::error::Error for ClaudeBinaryError {}
+
+/// The resolved Claude binary this server will invoke, cached after the
+/// first successful discovery so later jobs don't re-probe the filesystem
+/// or re-spawn `claude --version`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClaudeBinaryInfo {
+    pub path: PathBuf,
+    pub version: String,
+}
+
+/// Oldest Claude CLI version this server's `--output-format stream-json`
+/// handling has been validated against.
+const MIN_SUPPORTED_VERSION: (u64, u64, u64) = (1, 0, 0);
+
+/// The `claude` found on PATH (if any), followed by per-OS common install
+/// locations, to fall back to once any explicit override has been exhausted.
+fn candidate_claude_paths() -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+
+    if let Ok(path) = which::which("claude") {
+        candidates.push(path);
+    }
+
+    if cfg!(target_os = "windows") {
+        if let Ok(local_app_data) = std::env::var("LOCALAPPDATA") {
+            candidates.push(
+                PathBuf::from(local_app_data)
+                    .join("Programs")
+                    .join("claude")
+                    .join("claude.exe"),
+            );
+        }
+        if let Some(home) = dirs::home_dir() {
+            candidates.push(home.join("AppData\\Roaming\\npm\\claude.cmd"));
+        }
+    } else {
+        candidates.push(PathBuf::from("/usr/local/bin/claude"));
+        candidates.push(PathBuf::from("/opt/homebrew/bin/claude"));
+        if let Some(home) = dirs::home_dir() {
+            candidates.push(home.join(".local/bin/claude"));
+            candidates.push(home.join(".npm-global/bin/claude"));
         }
     }
 
-    Err("Claude binary not found. Please install Claude Code CLI.".to_string())
+    candidates
+}
+
+/// Resolve a usable Claude binary: an explicit override (config's
+/// `claude.binary_path` or the `OPCODE_CLAUDE_BINARY` env var) takes
+/// priority, then PATH, then common per-OS install locations. The winning
+/// candidate is verified by actually invoking `claude --version` rather
+/// than just checking the file exists, so a stale or broken binary is
+/// caught at discovery time instead of mid-execution.
+async fn discover_claude_binary(
+    override_path: Option<&str>,
+) -> Result<ClaudeBinaryInfo, ClaudeBinaryError> {
+    let mut candidates = Vec::new();
+    if let Ok(env_override) = std::env::var("OPCODE_CLAUDE_BINARY") {
+        candidates.push(PathBuf::from(env_override));
+    }
+    if let Some(configured) = override_path {
+        candidates.push(PathBuf::from(configured));
+    }
+    candidates.extend(candidate_claude_paths());
+
+    let mut last_error = ClaudeBinaryError::NotFound;
+    for candidate in candidates {
+        if !candidate.exists() {
+            continue;
+        }
+        match probe_version(&candidate).await {
+            Ok(version) => return Ok(ClaudeBinaryInfo { path: candidate, version }),
+            Err(err @ ClaudeBinaryError::UnsupportedVersion(_)) => return Err(err),
+            Err(err) => last_error = err,
+        }
+    }
+
+    Err(last_error)
+}
+
+/// Run `claude --version` and parse a semver-ish prefix out of its output.
+async fn probe_version(path: &std::path::Path) -> Result<String, ClaudeBinaryError> {
+    let output = Command::new(path)
+        .arg("--version")
+        .output()
+        .await
+        .map_err(|e| ClaudeBinaryError::NotExecutable(e.to_string()))?;
+
+    if !output.status.success() {
+        return Err(ClaudeBinaryError::NotExecutable(format!(
+            "exited with status {}",
+            output.status
+        )));
+    }
+
+    let version_output = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let parsed = parse_semver(&version_output).ok_or_else(|| {
+        ClaudeBinaryError::NotExecutable(format!(
+            "could not parse a version from: {version_output}"
+        ))
+    })?;
+
+    if parsed < MIN_SUPPORTED_VERSION {
+        return Err(ClaudeBinaryError::UnsupportedVersion(version_output));
+    }
+
+    Ok(version_output)
+}
+
+/// Pull the first `major.minor[.patch]`-shaped token out of `claude
+/// --version` output, e.g. `"1.2.3 (Claude Code)"` -> `(1, 2, 3)`.
+fn parse_semver(text: &str) -> Option<(u64, u64, u64)> {
+    let token = text
+        .split_whitespace()
+        .find(|token| token.starts_with(|c: char| c.is_ascii_digit()))?;
+    let mut parts = token.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().unwrap_or("0").parse().ok()?;
+    let patch = parts.next().unwrap_or("0").parse().ok()?;
+    Some((major, minor, patch))
 }
\ No newline at end of file